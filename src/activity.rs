@@ -0,0 +1,229 @@
+// Layout of the tiles uniform in activity.wgsl
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct TilesUniform {
+    columns: u32,
+    rows: u32,
+}
+
+// Workgroups per row of the compact and indirect dispatches, see ACTIVE_ROW in the shaders
+const ACTIVE_ROW: u32 = 256;
+
+// Skips the tiles of the texture kernel where nothing changed. Each generation
+// the transition flags the tiles owning cells that changed, activity.wgsl lists those
+// tiles and their neighbors, and only the listed tiles are dispatched. A skipped tile
+// keeps the state it had two generations ago in the write texture, which is the
+// current state since none of its neighbors changed.
+pub struct ActivityTracker {
+    tile_count: u32,
+    changed_buffer: wgpu::Buffer,
+    // Every tile flagged, copied over `changed_buffer` when `all_changed` is set
+    all_changed_buffer: wgpu::Buffer,
+    all_changed: bool,
+    // The active tile count followed by the indirect dispatch arguments
+    activity_buffer: wgpu::Buffer,
+    transition_pipeline: wgpu::ComputePipeline,
+    compact_pipeline: wgpu::ComputePipeline,
+    finalize_pipeline: wgpu::ComputePipeline,
+    compact_bind_group: wgpu::BindGroup,
+    transition_bind_group: wgpu::BindGroup,
+}
+
+impl ActivityTracker {
+    // `tile_size` is the edge of the tiles advanced by each workgroup, `transition_module`
+    // is the compiled transition.wgsl, `texture_layout` and `rule_layout` the layouts of
+    // its first two bind groups
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        (width, height): (u32, u32),
+        tile_size: u32,
+        transition_module: &wgpu::ShaderModule,
+        texture_layout: &wgpu::BindGroupLayout,
+        rule_layout: &wgpu::BindGroupLayout,
+    ) -> ActivityTracker {
+        let (columns, rows) = (width.div_ceil(tile_size), height.div_ceil(tile_size));
+        let tile_count = columns * rows;
+
+        // Create the buffers
+        let changed_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("changed_buffer"),
+            size: tile_count as u64 * 4,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let all_changed_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("all_changed_buffer"),
+            size: tile_count as u64 * 4,
+            usage: wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        queue.write_buffer(&all_changed_buffer, 0, bytemuck::cast_slice(&vec![1u32; tile_count as usize]));
+        let active_tiles_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("active_tiles_buffer"),
+            size: (tile_count + ACTIVE_ROW) as u64 * 4,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        let activity_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("activity_buffer"),
+            size: 16,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::INDIRECT | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let tiles_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("tiles_buffer"),
+            size: std::mem::size_of::<TilesUniform>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        queue.write_buffer(&tiles_buffer, 0, bytemuck::bytes_of(&TilesUniform { columns, rows }));
+
+        let buffer_entry = |binding, ty| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let storage = |read_only| wgpu::BufferBindingType::Storage { read_only };
+
+        // Create the compaction pipelines
+        let compact_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                buffer_entry(0, storage(true)),
+                buffer_entry(1, storage(false)),
+                buffer_entry(2, storage(false)),
+                buffer_entry(3, wgpu::BufferBindingType::Uniform),
+            ],
+            label: Some("compact_bind_group_layout"),
+        });
+        let compact_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("compact_pipeline_layout"),
+            bind_group_layouts: &[&compact_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let activity_shader_module = device.create_shader_module(wgpu::include_wgsl!("activity.wgsl"));
+        let create_compact_pipeline = |entry_point| device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some(entry_point),
+            layout: Some(&compact_pipeline_layout),
+            module: &activity_shader_module,
+            entry_point,
+            compilation_options: Default::default(),
+        });
+        let compact_pipeline = create_compact_pipeline("compact");
+        let finalize_pipeline = create_compact_pipeline("finalize");
+        let compact_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &compact_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: changed_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: active_tiles_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: activity_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: tiles_buffer.as_entire_binding(),
+                },
+            ],
+            label: Some("compact_bind_group"),
+        });
+
+        // Create the transition pipeline for the active tiles
+        let transition_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                buffer_entry(0, storage(false)),
+                buffer_entry(1, storage(true)),
+            ],
+            label: Some("active_transition_bind_group_layout"),
+        });
+        let transition_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("active_transition_pipeline_layout"),
+            bind_group_layouts: &[texture_layout, rule_layout, &transition_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let transition_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("active_transition_pipeline"),
+            layout: Some(&transition_pipeline_layout),
+            module: transition_module,
+            entry_point: "main_active",
+            compilation_options: Default::default(),
+        });
+        let transition_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &transition_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: changed_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: active_tiles_buffer.as_entire_binding(),
+                },
+            ],
+            label: Some("active_transition_bind_group"),
+        });
+
+        ActivityTracker {
+            tile_count,
+            changed_buffer,
+            all_changed_buffer,
+            all_changed: true,
+            activity_buffer,
+            transition_pipeline,
+            compact_pipeline,
+            finalize_pipeline,
+            compact_bind_group,
+            transition_bind_group,
+        }
+    }
+
+    // Makes every tile active in the next generation, for when the grid or the rule was
+    // changed from outside the transition
+    pub fn mark_all_changed(&mut self) {
+        self.all_changed = true;
+    }
+
+    // Records one generation of the active tiles
+    pub fn encode_generation(&mut self, encoder: &mut wgpu::CommandEncoder, textures: &wgpu::BindGroup, rule: &wgpu::BindGroup) {
+        if self.all_changed {
+            encoder.copy_buffer_to_buffer(&self.all_changed_buffer, 0, &self.changed_buffer, 0, self.tile_count as u64 * 4);
+            self.all_changed = false;
+        }
+        encoder.clear_buffer(&self.activity_buffer, 0, None);
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("compact_compute_pass"),
+                timestamp_writes: None,
+            });
+            compute_pass.set_pipeline(&self.compact_pipeline);
+            compute_pass.set_bind_group(0, &self.compact_bind_group, &[]);
+            let workgroups = self.tile_count.div_ceil(64);
+            compute_pass.dispatch_workgroups(workgroups.min(ACTIVE_ROW), workgroups.div_ceil(ACTIVE_ROW), 1);
+            compute_pass.set_pipeline(&self.finalize_pipeline);
+            compute_pass.dispatch_workgroups(1, 1, 1);
+        }
+
+        // The transition flags the tiles that change in this generation
+        encoder.clear_buffer(&self.changed_buffer, 0, None);
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("active_transition_compute_pass"),
+            timestamp_writes: None,
+        });
+        compute_pass.set_pipeline(&self.transition_pipeline);
+        compute_pass.set_bind_group(0, textures, &[]);
+        compute_pass.set_bind_group(1, rule, &[]);
+        compute_pass.set_bind_group(2, &self.transition_bind_group, &[]);
+        compute_pass.dispatch_workgroups_indirect(&self.activity_buffer, 4);
+    }
+}
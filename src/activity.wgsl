@@ -0,0 +1,64 @@
+// Builds the list of tiles for main_active in transition.wgsl: every tile that changed
+// in the last generation along with its eight neighbors.
+struct Tiles {
+    columns: u32,
+    rows: u32,
+};
+
+// `count` is filled by compact, the dispatch size by finalize
+struct Activity {
+    count: atomic<u32>,
+    dispatch_x: u32,
+    dispatch_y: u32,
+    dispatch_z: u32,
+};
+
+@group(0) @binding(0) var<storage, read> changed: array<u32>;
+@group(0) @binding(1) var<storage, read_write> active_tiles: array<u32>;
+@group(0) @binding(2) var<storage, read_write> activity: Activity;
+@group(0) @binding(3) var<uniform> tiles: Tiles;
+
+const NO_TILE: u32 = 0xffffffffu;
+const ACTIVE_ROW: u32 = 256u;
+
+// Dispatched in rows of ACTIVE_ROW workgroups to stay within the dispatch size limit
+@compute
+@workgroup_size(64)
+fn compact(
+    @builtin(workgroup_id) workgroup_id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+) {
+    let index = (workgroup_id.y * ACTIVE_ROW + workgroup_id.x) * 64u + local_index;
+    if index >= tiles.columns * tiles.rows {
+        return;
+    }
+
+    let x = index % tiles.columns;
+    let y = index / tiles.columns;
+    var is_active = false;
+    for (var dy = 0u; dy < 3u; dy++) {
+        for (var dx = 0u; dx < 3u; dx++) {
+            let neighbor_x = (x + tiles.columns + dx - 1u) % tiles.columns;
+            let neighbor_y = (y + tiles.rows + dy - 1u) % tiles.rows;
+            is_active = is_active || changed[neighbor_y * tiles.columns + neighbor_x] != 0u;
+        }
+    }
+
+    if is_active {
+        active_tiles[atomicAdd(&activity.count, 1u)] = index;
+    }
+}
+
+// Lays the active tiles out in rows of ACTIVE_ROW workgroups, padding the last row
+@compute
+@workgroup_size(1)
+fn finalize() {
+    let count = atomicLoad(&activity.count);
+    let rows = (count + ACTIVE_ROW - 1u) / ACTIVE_ROW;
+    activity.dispatch_x = min(count, ACTIVE_ROW);
+    activity.dispatch_y = rows;
+    activity.dispatch_z = 1u;
+    for (var index = count; index < rows * ACTIVE_ROW; index++) {
+        active_tiles[index] = NO_TILE;
+    }
+}
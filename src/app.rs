@@ -1,52 +1,122 @@
 use std::sync::Arc;
 use winit::{
     application::ApplicationHandler,
-    dpi::PhysicalSize, event::*,
+    dpi::{PhysicalPosition, PhysicalSize}, event::*,
     event_loop::ActiveEventLoop,
     keyboard::{KeyCode, PhysicalKey},
     window::{Window, WindowId}
 };
-use std::time::Instant;
-use crate::render::RenderState;
+use std::time::{Duration, Instant};
+use wgpu_automata::{random_seed, Anchor, Boundary, Cell, Generator, Pattern};
+use crate::config::Config;
+use crate::playback::Playback;
+use crate::render::{DrawError, RenderState};
+
+// How often the grid is read back to survive losing the device
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(10);
+
+// Rules cycled through with the R key
+const RULE_PRESETS: &[&str] = &[
+    "B3/S23",       // Conway's Game of Life
+    "B36/S23",      // HighLife
+    "B3678/S34678", // Day & Night
+    "B2/S",         // Seeds
+];
 
 #[derive(Default)]
 pub struct App<'a> {
+    config: Config,
     window: Option<Arc<Window>>,
     state: Option<RenderState<'a>>,
+    rule_index: usize,
+    playback: Playback,
+    cursor_position: Option<PhysicalPosition<f64>>,
+    // The cell painted while a mouse button is held and the last texel painted
+    painting: Option<(Cell, (i64, i64))>,
+    panning: bool,
     last_now: Option<Instant>,
+    last_snapshot: Option<Instant>,
     sum_frame_time: u128,
     frame_count: u128,
 }
 
+impl App<'_> {
+    pub fn new(config: Config) -> Self {
+        App {
+            config,
+            ..App::default()
+        }
+    }
+}
+
 impl ApplicationHandler for App<'_> {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
         println!("App resumed");
         if self.window.is_none() {
             println!("Creating window and renderer");
 
-            let window = Arc::new(event_loop.create_window(Window::default_attributes()
+            let window = match event_loop.create_window(Window::default_attributes()
                 .with_title("wgpu automata")
                 .with_inner_size(PhysicalSize {
-                    width: 1024u32,
-                    height: 1024u32,
+                    width: self.config.window_size.0,
+                    height: self.config.window_size.1,
                 })
-            ).unwrap());
+            ) {
+                Ok(window) => Arc::new(window),
+                Err(err) => {
+                    eprintln!("error: failed to create a window: {}", err);
+                    event_loop.exit();
+                    return;
+                },
+            };
             self.window = Some(window.clone());
 
-            let mut state = pollster::block_on(RenderState::new(window.clone()));
-            state.create_pipelines();
-            state.randomize();
+            let pattern = match self.config.pattern.as_ref().map(Pattern::load).transpose() {
+                Ok(pattern) => pattern,
+                Err(err) => {
+                    eprintln!("error: {}: {}", self.config.pattern.as_ref().unwrap().display(), err);
+                    event_loop.exit();
+                    return;
+                },
+            };
+            let options = self.config.simulation_options(pattern.as_ref());
+            let builder = RenderState::builder(window.clone())
+                .simulation(options)
+                .chunk_size(self.config.chunk_size)
+                .unbounded(self.config.unbounded)
+                .present_mode(self.config.present_mode);
+            let mut state = match pollster::block_on(builder.build()) {
+                Ok(state) => state,
+                Err(err) => {
+                    eprintln!("error: {}", err);
+                    event_loop.exit();
+                    return;
+                },
+            };
+            let placed = match &pattern {
+                Some(pattern) => state.center_pattern(pattern),
+                None => {
+                    let seed = self.config.seed.unwrap_or_else(random_seed);
+                    println!("Generating {} with seed {} and density {}", self.config.generator, seed, self.config.density);
+                    state.randomize(self.config.generator, seed, self.config.density)
+                },
+            };
+            if let Err(err) = placed {
+                eprintln!("error: {}", err);
+                event_loop.exit();
+                return;
+            }
             self.state = Some(state);
         }
     }
     
     fn window_event(&mut self, event_loop: &ActiveEventLoop, id: WindowId, event: WindowEvent) {
-        if id != self.window.as_ref().unwrap().id() {
+        let Some(window) = self.window.as_ref() else { return };
+        if id != window.id() {
             return;
         }
-
-        let window = self.window.as_ref().unwrap();
-        let state = self.state.as_mut().unwrap();
+        // Events queued for the window can still arrive after `resumed` failed and exited
+        let Some(state) = self.state.as_mut() else { return };
 
         match event {
             WindowEvent::CloseRequested => {
@@ -58,9 +128,42 @@ impl ApplicationHandler for App<'_> {
                 state.resize(physical_size);
             },
             WindowEvent::RedrawRequested => {
-                state.draw();
-                if self.last_now.is_some() {
-                    self.sum_frame_time += self.last_now.unwrap().elapsed().as_micros();
+                if state.space().is_device_lost() {
+                    println!("Recreating the device");
+                    if let Err(err) = state.recover() {
+                        eprintln!("error: {}", err);
+                        event_loop.exit();
+                        return;
+                    }
+                }
+                // Snapshots are read back over the following frames
+                state.poll_snapshot();
+                if self.last_snapshot.is_none_or(|last_snapshot| last_snapshot.elapsed() >= SNAPSHOT_INTERVAL) {
+                    state.take_snapshot();
+                    self.last_snapshot = Some(Instant::now());
+                }
+
+                match state.draw(self.playback.next_frame()) {
+                    Ok(()) => {},
+                    Err(DrawError::Surface(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated)) => state.reconfigure(),
+                    Err(DrawError::Surface(wgpu::SurfaceError::Timeout)) => println!("Surface timed out, skipping frame"),
+                    Err(DrawError::Surface(wgpu::SurfaceError::OutOfMemory)) => {
+                        println!("Out of memory");
+                        event_loop.exit();
+                    },
+                    Err(DrawError::Simulation(err)) => {
+                        eprintln!("error: {}", err);
+                        event_loop.exit();
+                        return;
+                    },
+                }
+                if let Some(err) = state.space().take_error() {
+                    eprintln!("error: {}", err);
+                    event_loop.exit();
+                    return;
+                }
+                if let Some(last_now) = self.last_now {
+                    self.sum_frame_time += last_now.elapsed().as_micros();
                     self.frame_count += 1;
                     if self.frame_count == 100 {
                         println!("{} fps", 1e+6f32 / (self.sum_frame_time / self.frame_count) as f32);
@@ -71,6 +174,54 @@ impl ApplicationHandler for App<'_> {
                 self.last_now = Some(Instant::now());
                 window.request_redraw();
             },
+            WindowEvent::CursorMoved { position, .. } => {
+                if let (true, Some(last_position)) = (self.panning, self.cursor_position) {
+                    state.pan((position.x - last_position.x, position.y - last_position.y));
+                }
+                self.cursor_position = Some(position);
+                if let Some((cell, last_texel)) = self.painting {
+                    let texel = state.surface_to_texel(position);
+                    match state.paint_line(last_texel, texel, cell) {
+                        Ok(()) => self.painting = Some((cell, texel)),
+                        Err(err) => {
+                            println!("Failed to paint: {}", err);
+                            self.painting = None;
+                        },
+                    }
+                }
+            },
+            WindowEvent::CursorLeft { .. } => {
+                self.cursor_position = None;
+            },
+            WindowEvent::MouseInput { state: button_state, button: MouseButton::Middle, .. } => {
+                self.panning = button_state == ElementState::Pressed;
+            },
+            WindowEvent::MouseWheel { delta, .. } => {
+                let lines = match delta {
+                    MouseScrollDelta::LineDelta(_, lines) => lines as f64,
+                    MouseScrollDelta::PixelDelta(position) => position.y / 40.0,
+                };
+                if let Some(position) = self.cursor_position {
+                    state.zoom_around(position, 1.25f64.powf(lines));
+                }
+            },
+            WindowEvent::MouseInput { state: button_state, button, .. } => {
+                let cell = match button {
+                    MouseButton::Left => Cell::ALIVE,
+                    MouseButton::Right => Cell::DEAD,
+                    _ => return,
+                };
+                match (button_state, self.cursor_position) {
+                    (ElementState::Pressed, Some(position)) => {
+                        let texel = state.surface_to_texel(position);
+                        match state.paint_line(texel, texel, cell) {
+                            Ok(()) => self.painting = Some((cell, texel)),
+                            Err(err) => println!("Failed to paint: {}", err),
+                        }
+                    },
+                    _ => self.painting = None,
+                }
+            },
             WindowEvent::KeyboardInput {
                 event:
                     KeyEvent {
@@ -94,8 +245,164 @@ impl ApplicationHandler for App<'_> {
                     },
                 ..
             } => {
-                println!("Space key pressed!");
-                state.randomize();
+                let seed = random_seed();
+                println!("Generating {} with seed {}", self.config.generator, seed);
+                if let Err(err) = state.randomize(self.config.generator, seed, self.config.density) {
+                    println!("Failed to generate {}: {}", self.config.generator, err);
+                }
+            },
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(KeyCode::KeyG),
+                        state: ElementState::Pressed,
+                        repeat: false,
+                        ..
+                    },
+                ..
+            } => {
+                let index = Generator::ALL.iter().position(|&generator| generator == self.config.generator);
+                self.config.generator = Generator::ALL[index.map_or(0, |index| (index + 1) % Generator::ALL.len())];
+                let seed = random_seed();
+                println!("Generating {} with seed {}", self.config.generator, seed);
+                if let Err(err) = state.randomize(self.config.generator, seed, self.config.density) {
+                    println!("Failed to generate {}: {}", self.config.generator, err);
+                }
+            },
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(KeyCode::KeyP),
+                        state: ElementState::Pressed,
+                        repeat: false,
+                        ..
+                    },
+                ..
+            } => {
+                self.playback.paused = !self.playback.paused;
+                println!("{}", if self.playback.paused { "Paused" } else { "Resumed" });
+            },
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(KeyCode::KeyN),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                self.playback.single_step();
+            },
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(key @ (KeyCode::Equal | KeyCode::Minus)),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                self.playback.rate = match key {
+                    KeyCode::Equal => self.playback.rate.faster(),
+                    _ => self.playback.rate.slower(),
+                };
+                println!("Running {}", self.playback.rate);
+            },
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(KeyCode::Home),
+                        state: ElementState::Pressed,
+                        repeat: false,
+                        ..
+                    },
+                ..
+            } => {
+                state.reset_camera();
+            },
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(KeyCode::KeyE),
+                        state: ElementState::Pressed,
+                        repeat: false,
+                        ..
+                    },
+                ..
+            } => {
+                let path = format!("generation-{}.rle", state.space().generation());
+                let saved = match state.export_pattern() {
+                    Ok(pattern) => pattern.save(&path).map_err(|err| err.to_string()),
+                    Err(err) => Err(err.to_string()),
+                };
+                match saved {
+                    Ok(()) => println!("Exported {} to {}", state.space().rule(), path),
+                    Err(err) => println!("Failed to export {}: {}", path, err),
+                }
+            },
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(KeyCode::KeyR),
+                        state: ElementState::Pressed,
+                        repeat: false,
+                        ..
+                    },
+                ..
+            } => {
+                self.rule_index = (self.rule_index + 1) % RULE_PRESETS.len();
+                let rule = RULE_PRESETS[self.rule_index].parse().unwrap();
+                match state.set_rule(rule) {
+                    Ok(()) => println!("Switching to rule {}", rule),
+                    Err(err) => println!("Failed to switch to rule {}: {}", rule, err),
+                }
+            },
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(KeyCode::KeyB),
+                        state: ElementState::Pressed,
+                        repeat: false,
+                        ..
+                    },
+                ..
+            } => {
+                let index = Boundary::ALL.iter().position(|&boundary| boundary == self.config.boundary);
+                let boundary = Boundary::ALL[index.map_or(0, |index| (index + 1) % Boundary::ALL.len())];
+                match state.set_boundary(boundary) {
+                    Ok(()) => {
+                        println!("Switching to the {} boundary", boundary);
+                        self.config.boundary = boundary;
+                    },
+                    Err(err) => println!("Failed to switch to the {} boundary: {}", boundary, err),
+                }
+            },
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(key @ (KeyCode::BracketRight | KeyCode::BracketLeft)),
+                        state: ElementState::Pressed,
+                        repeat: false,
+                        ..
+                    },
+                ..
+            } => {
+                // Doubles or halves the grid around its center
+                let Some((width, height)) = state.space().torus_size() else {
+                    println!("An unbounded universe cannot be resized");
+                    return;
+                };
+                let size = match key {
+                    KeyCode::BracketRight => (width * 2, height * 2),
+                    _ => ((width / 2).max(1), (height / 2).max(1)),
+                };
+                match state.resize_grid(size, Anchor::Center) {
+                    Ok(()) => {
+                        println!("Resized the grid to {}x{}", size.0, size.1);
+                        self.config.grid_size = size;
+                    },
+                    Err(err) => println!("Failed to resize the grid: {}", err),
+                }
             },
             _ => {},
         }
@@ -0,0 +1,205 @@
+use crate::format::CellFormat;
+
+// Layout of the grid uniform in bits.wgsl
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct GridUniform {
+    words_per_row: u32,
+    height: u32,
+}
+
+// The bit-packed transition kernel. Cells are stored 32 to a u32 in a pair of storage
+// buffers that ping-pong like `TextureSwapper`. The cell textures are still used for
+// uploads, readback and display: `encode_pack` copies the current texture into the
+// words and `encode_unpack` copies the words back out.
+pub struct BitKernel {
+    size: (u32, u32),
+    // Buffer holding the current generation
+    index: usize,
+    step_pipeline: wgpu::ComputePipeline,
+    pack_pipeline: wgpu::ComputePipeline,
+    unpack_pipeline: wgpu::ComputePipeline,
+    // Bind group i reads buffer i and writes the other one
+    step_bind_groups: [wgpu::BindGroup; 2],
+    // Bind group i binds buffer i for packing and unpacking
+    word_bind_groups: [wgpu::BindGroup; 2],
+}
+
+impl BitKernel {
+    // `texture_layout` and `rule_layout` are the layouts of the transition and rule bind
+    // groups of the texture kernel. The width must be a multiple of 32, which
+    // `Simulation::with_instance` checks.
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        (width, height): (u32, u32),
+        format: CellFormat,
+        texture_layout: &wgpu::BindGroupLayout,
+        rule_layout: &wgpu::BindGroupLayout,
+    ) -> BitKernel {
+        let words_per_row = width / 32;
+
+        // Create the word buffers
+        let create_word_buffer = |label| device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size: (words_per_row * height) as u64 * std::mem::size_of::<u32>() as u64,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        let word_buffers = [create_word_buffer("word_buffer_a"), create_word_buffer("word_buffer_b")];
+
+        // Create and upload the grid uniform
+        let grid_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("bits_grid_buffer"),
+            size: std::mem::size_of::<GridUniform>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        queue.write_buffer(&grid_buffer, 0, bytemuck::bytes_of(&GridUniform { words_per_row, height }));
+
+        let storage_entry = |binding, read_only| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+
+        // Create the step pipeline
+        let step_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                storage_entry(0, true),
+                storage_entry(1, false),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+            label: Some("bits_step_bind_group_layout"),
+        });
+        let step_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("bits_step_pipeline_layout"),
+            bind_group_layouts: &[&step_bind_group_layout, rule_layout],
+            push_constant_ranges: &[],
+        });
+        let step_shader_module = device.create_shader_module(wgpu::include_wgsl!("bits.wgsl"));
+        let step_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("bits_step_pipeline"),
+            layout: Some(&step_pipeline_layout),
+            module: &step_shader_module,
+            entry_point: "main",
+            compilation_options: Default::default(),
+        });
+        let step_bind_groups = [0, 1].map(|index| device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &step_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: word_buffers[index].as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: word_buffers[1 - index].as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: grid_buffer.as_entire_binding(),
+                },
+            ],
+            label: Some("bits_step_bind_group"),
+        }));
+
+        // Create the pack and unpack pipelines, which share the transition bind groups
+        let word_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[storage_entry(0, false)],
+            label: Some("word_bind_group_layout"),
+        });
+        let packing_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("packing_pipeline_layout"),
+            bind_group_layouts: &[texture_layout, &word_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let packing_shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("packing.wgsl"),
+            source: wgpu::ShaderSource::Wgsl(format.shader_source(include_str!("packing.wgsl")).into()),
+        });
+        let create_packing_pipeline = |entry_point| device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some(entry_point),
+            layout: Some(&packing_pipeline_layout),
+            module: &packing_shader_module,
+            entry_point,
+            compilation_options: Default::default(),
+        });
+        let pack_pipeline = create_packing_pipeline("pack");
+        let unpack_pipeline = create_packing_pipeline("unpack");
+        let word_bind_groups = [0, 1].map(|index| device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &word_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: word_buffers[index].as_entire_binding(),
+                },
+            ],
+            label: Some("word_bind_group"),
+        }));
+
+        BitKernel {
+            size: (width, height),
+            index: 0,
+            step_pipeline,
+            pack_pipeline,
+            unpack_pipeline,
+            step_bind_groups,
+            word_bind_groups,
+        }
+    }
+
+    // Records a copy of the input texture of `textures` into the current words
+    pub fn encode_pack(&self, encoder: &mut wgpu::CommandEncoder, textures: &wgpu::BindGroup) {
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("pack_compute_pass"),
+            timestamp_writes: None,
+        });
+        compute_pass.set_pipeline(&self.pack_pipeline);
+        compute_pass.set_bind_group(0, textures, &[]);
+        compute_pass.set_bind_group(1, &self.word_bind_groups[self.index], &[]);
+        compute_pass.dispatch_workgroups((self.size.0 / 32).div_ceil(8), self.size.1.div_ceil(8), 1);
+    }
+
+    // Records the given number of generations
+    pub fn encode_steps(&mut self, encoder: &mut wgpu::CommandEncoder, generations: u32, rule: &wgpu::BindGroup) {
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("bits_compute_pass"),
+            timestamp_writes: None,
+        });
+        compute_pass.set_pipeline(&self.step_pipeline);
+        compute_pass.set_bind_group(1, rule, &[]);
+        for _ in 0..generations {
+            compute_pass.set_bind_group(0, &self.step_bind_groups[self.index], &[]);
+            compute_pass.dispatch_workgroups((self.size.0 / 32).div_ceil(8), self.size.1.div_ceil(8), 1);
+            self.index = 1 - self.index;
+        }
+    }
+
+    // Records a copy of the current words into the output texture of `textures`, taking
+    // the trails from its input texture
+    pub fn encode_unpack(&self, encoder: &mut wgpu::CommandEncoder, textures: &wgpu::BindGroup) {
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("unpack_compute_pass"),
+            timestamp_writes: None,
+        });
+        compute_pass.set_pipeline(&self.unpack_pipeline);
+        compute_pass.set_bind_group(0, textures, &[]);
+        compute_pass.set_bind_group(1, &self.word_bind_groups[self.index], &[]);
+        compute_pass.dispatch_workgroups(self.size.0.div_ceil(16), self.size.1.div_ceil(16), 1);
+    }
+}
@@ -0,0 +1,74 @@
+struct Rule {
+    birth: u32,
+    survival: u32,
+};
+
+struct Grid {
+    words_per_row: u32,
+    height: u32,
+};
+
+// Bit i of word x in a row holds the cell at column 32 * x + i
+@group(0) @binding(0) var<storage, read> input_words: array<u32>;
+@group(0) @binding(1) var<storage, read_write> output_words: array<u32>;
+@group(0) @binding(2) var<uniform> grid: Grid;
+@group(1) @binding(0) var<uniform> rule: Rule;
+
+// Bit-sliced neighbor counts of 32 cells, component b holds bit b of every count
+var<private> count: vec4<u32>;
+
+// Adds one neighbor to the 32 counts in parallel with a chain of half adders
+fn add_neighbors(neighbors: u32) {
+    var carry = neighbors;
+    for (var b = 0u; b < 4u; b++) {
+        let next_carry = count[b] & carry;
+        count[b] ^= carry;
+        carry = next_carry;
+    }
+}
+
+fn load_word(x: u32, y: u32) -> u32 {
+    return input_words[y * grid.words_per_row + x];
+}
+
+@compute
+@workgroup_size(8, 8)
+fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    if global_id.x >= grid.words_per_row || global_id.y >= grid.height {
+        return;
+    }
+
+    let left = (global_id.x + grid.words_per_row - 1u) % grid.words_per_row;
+    let right = (global_id.x + 1u) % grid.words_per_row;
+    var rows = array<u32, 3>(
+        (global_id.y + grid.height - 1u) % grid.height,
+        global_id.y,
+        (global_id.y + 1u) % grid.height,
+    );
+
+    count = vec4<u32>(0u);
+    for (var i = 0u; i < 3u; i++) {
+        let center = load_word(global_id.x, rows[i]);
+        // Shift in the neighboring bits of the words to the left and right
+        add_neighbors((center << 1u) | (load_word(left, rows[i]) >> 31u));
+        add_neighbors((center >> 1u) | (load_word(right, rows[i]) << 31u));
+        if i != 1u {
+            add_neighbors(center);
+        }
+    }
+
+    // Apply the birth and survival rules for every possible count
+    let alive = load_word(global_id.x, global_id.y);
+    var next = 0u;
+    for (var n = 0u; n <= 8u; n++) {
+        var equal = 0xffffffffu;
+        for (var b = 0u; b < 4u; b++) {
+            equal &= select(~count[b], count[b], ((n >> b) & 1u) != 0u);
+        }
+        let born = select(0u, ~alive, ((rule.birth >> n) & 1u) != 0u);
+        let survives = select(0u, alive, ((rule.survival >> n) & 1u) != 0u);
+        next |= equal & (born | survives);
+    }
+
+    output_words[global_id.y * grid.words_per_row + global_id.x] = next;
+}
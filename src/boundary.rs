@@ -0,0 +1,188 @@
+//! How the edges of a grid are joined.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// How the edges of the grid are joined. The transition kernel and the CPU reference
+/// both find the cells beyond the edges with `locate`, see `locate` in transition.wgsl.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Boundary {
+    /// Opposite edges are joined
+    #[default]
+    Torus,
+    /// Cells beyond the edges are always dead
+    Dead,
+    /// The edges reflect the grid, so cells beyond an edge mirror the cells inside
+    Mirror,
+    /// The left and right edges are joined, the top and bottom edges with a half twist
+    Klein,
+    /// Both pairs of edges are joined with a half twist. Only one generation per dispatch
+    /// is advanced on this surface, see `SimulationOptions::block_generations`.
+    Cross,
+}
+
+/// Layout of the boundary uniform in transition.wgsl
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct BoundaryUniform {
+    mode: u32,
+    _padding: [u32; 3],
+}
+
+impl Boundary {
+    /// Every boundary, in the order they are cycled through
+    pub const ALL: [Boundary; 5] = [Boundary::Torus, Boundary::Dead, Boundary::Mirror, Boundary::Klein, Boundary::Cross];
+
+    pub(crate) fn uniform(self) -> BoundaryUniform {
+        // Matches the mode constants in transition.wgsl
+        let mode = match self {
+            Boundary::Torus => 0,
+            Boundary::Dead => 1,
+            Boundary::Mirror => 2,
+            Boundary::Klein => 3,
+            Boundary::Cross => 4,
+        };
+        BoundaryUniform { mode, _padding: [0; 3] }
+    }
+
+    /// The cell of a `width` by `height` grid found at a position that may be beyond the
+    /// edges, or None for a dead cell beyond a dead edge
+    pub fn locate(self, (x, y): (i64, i64), (width, height): (u32, u32)) -> Option<(u32, u32)> {
+        let (width, height) = (width as i64, height as i64);
+        let located = match self {
+            Boundary::Torus => (x.rem_euclid(width), y.rem_euclid(height)),
+            Boundary::Dead => {
+                if !(0..width).contains(&x) || !(0..height).contains(&y) {
+                    return None;
+                }
+                (x, y)
+            },
+            Boundary::Mirror => (reflect(x, width), reflect(y, height)),
+            Boundary::Klein => {
+                let x = x.rem_euclid(width);
+                let twisted = y.div_euclid(height) % 2 != 0;
+                (if twisted { width - 1 - x } else { x }, y.rem_euclid(height))
+            },
+            Boundary::Cross => {
+                // Crossing the left or right edge flips y, then crossing the top or
+                // bottom edge flips x
+                let y = if x.div_euclid(width) % 2 != 0 { height - 1 - y } else { y };
+                let x = x.rem_euclid(width);
+                let x = if y.div_euclid(height) % 2 != 0 { width - 1 - x } else { x };
+                (x, y.rem_euclid(height))
+            },
+        };
+        Some((located.0 as u32, located.1 as u32))
+    }
+}
+
+// Mirrors a coordinate into 0..size, each edge reflecting the cells next to it
+fn reflect(position: i64, size: i64) -> i64 {
+    let position = position.rem_euclid(2 * size);
+    if position < size { position } else { 2 * size - 1 - position }
+}
+
+impl fmt::Display for Boundary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Boundary::Torus => "torus",
+            Boundary::Dead => "dead",
+            Boundary::Mirror => "mirror",
+            Boundary::Klein => "klein",
+            Boundary::Cross => "cross",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+/// A boundary name that is not one of torus, dead, mirror, klein or cross
+pub struct ParseBoundaryError(String);
+
+impl fmt::Display for ParseBoundaryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown boundary '{}', expected torus, dead, mirror, klein or cross", self.0)
+    }
+}
+
+impl std::error::Error for ParseBoundaryError {}
+
+impl FromStr for Boundary {
+    type Err = ParseBoundaryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_ascii_lowercase();
+        Boundary::ALL.iter()
+            .copied()
+            .find(|boundary| boundary.to_string() == name)
+            .ok_or_else(|| ParseBoundaryError(s.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::CellFormat;
+    use crate::generator::Generator;
+    use crate::reference::{first_difference, step_n};
+    use crate::rule::Rule;
+    use crate::simulation::{Kernel, SimulationOptions};
+    use crate::testing::{gpu_simulation_with, grid_with, GLIDER};
+
+    #[test]
+    fn edges_are_joined() {
+        let size = (4, 3);
+        for boundary in Boundary::ALL {
+            assert_eq!(boundary.locate((2, 1), size), Some((2, 1)), "{}", boundary);
+        }
+        assert_eq!(Boundary::Torus.locate((-1, 3), size), Some((3, 0)));
+        assert_eq!(Boundary::Dead.locate((-1, 0), size), None);
+        assert_eq!(Boundary::Mirror.locate((-1, 4), size), Some((0, 1)));
+        assert_eq!(Boundary::Mirror.locate((9, -5), size), Some((1, 1)));
+        assert_eq!(Boundary::Klein.locate((4, 1), size), Some((0, 1)));
+        assert_eq!(Boundary::Klein.locate((1, -1), size), Some((2, 2)));
+        assert_eq!(Boundary::Cross.locate((-1, 0), size), Some((3, 2)));
+        assert_eq!(Boundary::Cross.locate((1, 3), size), Some((2, 0)));
+        assert_eq!(Boundary::Cross.locate((-1, -1), size), Some((0, 0)));
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter, run with --ignored"]
+    fn boundaries_match() {
+        // A soup running into every edge, and a glider crossing the bottom edge far from
+        // the tiles next to it on the torus. The boundary switches to the next one halfway.
+        let size = (96, 29);
+        let soup = Generator::Soup.generate(size, 5, 0.35);
+        let glider = grid_with(size.0, size.1, GLIDER, (17, 22));
+        let kernels = [(Kernel::Texture, false, 1), (Kernel::Texture, true, 1), (Kernel::Texture, false, 3), (Kernel::BitPacked, false, 1)];
+        for grid in [&soup, &glider] {
+            for (index, &boundary) in Boundary::ALL.iter().enumerate() {
+                let next = Boundary::ALL[(index + 1) % Boundary::ALL.len()];
+                let expected = step_n(grid, Rule::CONWAY, boundary, 30);
+                let expected = step_n(&expected, Rule::CONWAY, next, 30);
+                for (kernel, skip_stable_tiles, block_generations) in kernels {
+                    let mut simulation = gpu_simulation_with(SimulationOptions {
+                        size,
+                        boundary,
+                        format: CellFormat::Uint32,
+                        kernel,
+                        skip_stable_tiles,
+                        block_generations,
+                        ..SimulationOptions::default()
+                    });
+                    simulation.set_grid(grid);
+                    simulation.step(30);
+                    simulation.set_boundary(next);
+                    simulation.step(30);
+                    let actual = simulation.read_cells_blocking().unwrap();
+                    assert_eq!(
+                        first_difference(&expected, &actual),
+                        None,
+                        "{} then {} disagree with the {} kernel, skipping stable tiles: {}, blocks of {}",
+                        boundary, next, kernel, skip_stable_tiles, block_generations,
+                    );
+                }
+            }
+        }
+    }
+}
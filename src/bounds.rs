@@ -0,0 +1,127 @@
+use crate::format::CellFormat;
+
+// Layout of the bounds buffer in bounds.wgsl, the margin followed by the corners
+const BOUNDS_SIZE: u64 = 5 * 4;
+
+// The pipeline of bounds.wgsl, shared by every `BoundsFinder` of a format
+pub struct BoundsPipeline {
+    pipeline: wgpu::ComputePipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+}
+
+impl BoundsPipeline {
+    // `texture_layout` is the layout of the transition bind groups
+    pub fn new(device: &wgpu::Device, format: CellFormat, texture_layout: &wgpu::BindGroupLayout) -> BoundsPipeline {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+            label: Some("bounds_bind_group_layout"),
+        });
+
+        // The pipeline reads the input texture of the transition bind groups
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("bounds_pipeline_layout"),
+            bind_group_layouts: &[texture_layout, &bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("bounds.wgsl"),
+            source: wgpu::ShaderSource::Wgsl(format.shader_source(include_str!("bounds.wgsl")).into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("bounds_pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader_module,
+            entry_point: "main",
+            compilation_options: Default::default(),
+        });
+
+        BoundsPipeline {
+            pipeline,
+            bind_group_layout,
+        }
+    }
+}
+
+// Finds the rectangle holding the live cells of a texture without reading the texture
+// back, see bounds.wgsl. The corners are copied into a buffer the caller maps, so the
+// corners of many textures can be read back at once.
+pub struct BoundsFinder {
+    bounds_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+impl BoundsFinder {
+    // The live cells within `margin` texels of the edges are ignored
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, pipeline: &BoundsPipeline, margin: u32) -> BoundsFinder {
+        let bounds_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("bounds_buffer"),
+            size: BOUNDS_SIZE,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        queue.write_buffer(&bounds_buffer, 0, bytemuck::bytes_of(&margin));
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &pipeline.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: bounds_buffer.as_entire_binding(),
+                },
+            ],
+            label: Some("bounds_bind_group"),
+        });
+
+        BoundsFinder {
+            bounds_buffer,
+            bind_group,
+        }
+    }
+
+    // Records finding the live cells of the input texture of `textures` with `pipeline`
+    // and copying the corners into `target` at `offset`, see `decode`
+    pub fn encode(
+        &self,
+        pipeline: &BoundsPipeline,
+        encoder: &mut wgpu::CommandEncoder,
+        textures: &wgpu::BindGroup,
+        (width, height): (u32, u32),
+        target: &wgpu::Buffer,
+        offset: u64,
+    ) {
+        encoder.clear_buffer(&self.bounds_buffer, 4, None);
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("bounds_compute_pass"),
+                timestamp_writes: None,
+            });
+            compute_pass.set_pipeline(&pipeline.pipeline);
+            compute_pass.set_bind_group(0, textures, &[]);
+            compute_pass.set_bind_group(1, &self.bind_group, &[]);
+            compute_pass.dispatch_workgroups(width.div_ceil(16), height.div_ceil(16), 1);
+        }
+        encoder.copy_buffer_to_buffer(&self.bounds_buffer, 4, target, offset, CORNERS_SIZE);
+    }
+}
+
+// Bytes copied into the target buffer by `BoundsFinder::encode`
+pub const CORNERS_SIZE: u64 = 4 * 4;
+
+// The lowest and highest texel holding a live cell from the words copied by `encode`,
+// or None when there are none. The lowest corner is stored inverted, so the buffer
+// starts out cleared.
+pub fn decode(words: &[u32]) -> Option<((u32, u32), (u32, u32))> {
+    let [inverted_min_x, inverted_min_y, max_x, max_y] = words.try_into().unwrap();
+    let (min_x, min_y) = (!inverted_min_x, !inverted_min_y);
+    (min_x <= max_x).then_some(((min_x, min_y), (max_x, max_y)))
+}
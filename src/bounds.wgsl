@@ -0,0 +1,49 @@
+// The lowest corner is stored inverted, so a cleared buffer holds no cells
+struct Bounds {
+    margin: u32, // texels skipped along each edge
+    inverted_min_x: atomic<u32>,
+    inverted_min_y: atomic<u32>,
+    max_x: atomic<u32>,
+    max_y: atomic<u32>,
+};
+
+@group(1) @binding(0) var<storage, read_write> bounds: Bounds;
+
+// The corners of the live cells of the workgroup, so each workgroup only updates the
+// buffer once
+var<workgroup> tile_min_x: atomic<u32>;
+var<workgroup> tile_min_y: atomic<u32>;
+var<workgroup> tile_max_x: atomic<u32>;
+var<workgroup> tile_max_y: atomic<u32>;
+
+@compute
+@workgroup_size(16, 16)
+fn main(
+    @builtin(global_invocation_id) global_id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+) {
+    if local_index == 0u {
+        atomicStore(&tile_min_x, 0xffffffffu);
+        atomicStore(&tile_min_y, 0xffffffffu);
+        atomicStore(&tile_max_x, 0u);
+        atomicStore(&tile_max_y, 0u);
+    }
+    workgroupBarrier();
+
+    let dimensions = textureDimensions(input_texture);
+    let inside = all(global_id.xy >= vec2<u32>(bounds.margin)) && all(global_id.xy + bounds.margin < dimensions);
+    if inside && load_cell(global_id.xy).r > 0.0 {
+        atomicMin(&tile_min_x, global_id.x);
+        atomicMin(&tile_min_y, global_id.y);
+        atomicMax(&tile_max_x, global_id.x);
+        atomicMax(&tile_max_y, global_id.y);
+    }
+    workgroupBarrier();
+
+    if local_index == 0u && atomicLoad(&tile_min_x) <= atomicLoad(&tile_max_x) {
+        atomicMax(&bounds.inverted_min_x, ~atomicLoad(&tile_min_x));
+        atomicMax(&bounds.inverted_min_y, ~atomicLoad(&tile_min_y));
+        atomicMax(&bounds.max_x, atomicLoad(&tile_max_x));
+        atomicMax(&bounds.max_y, atomicLoad(&tile_max_y));
+    }
+}
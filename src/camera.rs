@@ -0,0 +1,162 @@
+use winit::dpi::PhysicalPosition;
+
+const MIN_ZOOM: f64 = 1.0 / 16.0;
+const MAX_ZOOM: f64 = 64.0;
+
+// Layout of the camera uniform in display.wgsl
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct CameraUniform {
+    surface_size: [f32; 2],
+    offset: [i32; 2],
+    offset_fraction: [f32; 2],
+    first_copy: [i32; 2],
+    zoom: f32,
+    copy_columns: u32,
+}
+
+// Maps surface pixels to texels. `offset` is the texel shown at the bottom left corner
+// of the surface and `zoom` is the number of surface pixels per texel. Texel rows
+// increase upwards, matching vs_main in display.wgsl.
+#[derive(Copy, Clone, Debug)]
+pub struct Camera {
+    offset: (f64, f64),
+    zoom: f64,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Camera {
+            offset: (0.0, 0.0),
+            zoom: 1.0,
+        }
+    }
+}
+
+impl Camera {
+    pub fn uniform(&self, surface_size: (u32, u32), torus_size: Option<(u32, u32)>) -> CameraUniform {
+        let (first_copy, (copy_columns, _)) = self.visible_copies(surface_size, torus_size);
+        // The whole texels are sent apart from the fraction, so an unbounded plane can be
+        // panned far away without the f32 losing the position within a texel
+        let offset = (self.offset.0.floor(), self.offset.1.floor());
+        CameraUniform {
+            surface_size: [surface_size.0 as f32, surface_size.1 as f32],
+            offset: [offset.0 as i32, offset.1 as i32],
+            offset_fraction: [(self.offset.0 - offset.0) as f32, (self.offset.1 - offset.1) as f32],
+            first_copy: [first_copy.0, first_copy.1],
+            zoom: self.zoom as f32,
+            copy_columns,
+        }
+    }
+
+    // The lowest copy of a torus on the surface and the columns and rows of copies
+    // covering it, a single copy of an unbounded plane
+    pub fn visible_copies(&self, (surface_width, surface_height): (u32, u32), torus_size: Option<(u32, u32)>) -> ((i32, i32), (u32, u32)) {
+        let Some((width, height)) = torus_size else {
+            return ((0, 0), (1, 1));
+        };
+        let copies = |offset: f64, pixels: u32, size: u32| {
+            let first = (offset / size as f64).floor();
+            let last = ((offset + pixels as f64 / self.zoom) / size as f64).floor();
+            (first as i32, (last - first) as u32 + 1)
+        };
+        let (x, columns) = copies(self.offset.0, surface_width, width);
+        let (y, rows) = copies(self.offset.1, surface_height, height);
+        ((x, y), (columns, rows))
+    }
+
+    // The unwrapped texel position under a surface position
+    pub fn surface_to_texel(&self, position: PhysicalPosition<f64>, surface_height: u32) -> (f64, f64) {
+        (
+            self.offset.0 + position.x / self.zoom,
+            self.offset.1 + (surface_height as f64 - position.y) / self.zoom,
+        )
+    }
+
+    // Moves the view by a distance in surface pixels. On a torus the offset wraps around
+    // the grid so it can be panned endlessly without losing precision.
+    pub fn pan(&mut self, (dx, dy): (f64, f64), torus_size: Option<(u32, u32)>) {
+        self.offset.0 -= dx / self.zoom;
+        self.offset.1 += dy / self.zoom;
+        if let Some((width, height)) = torus_size {
+            self.offset.0 = self.offset.0.rem_euclid(width as f64);
+            self.offset.1 = self.offset.1.rem_euclid(height as f64);
+        }
+    }
+
+    // Scales the zoom by `factor`, keeping the texel under `position` in place
+    pub fn zoom_around(&mut self, position: PhysicalPosition<f64>, factor: f64, surface_height: u32, torus_size: Option<(u32, u32)>) {
+        let before = self.surface_to_texel(position, surface_height);
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        let after = self.surface_to_texel(position, surface_height);
+        self.offset.0 += before.0 - after.0;
+        self.offset.1 += before.1 - after.1;
+        self.pan((0.0, 0.0), torus_size);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near((ax, ay): (f64, f64), (bx, by): (f64, f64)) {
+        assert!((ax - bx).abs() < 1e-9 && (ay - by).abs() < 1e-9, "{:?} != {:?}", (ax, ay), (bx, by));
+    }
+
+    #[test]
+    fn zooming_keeps_the_texel_under_the_cursor() {
+        let cursor = PhysicalPosition::new(310.0, 95.0);
+        for torus_size in [None, Some((200, 150))] {
+            let mut camera = Camera::default();
+            camera.pan((-40.0, 25.0), torus_size);
+            let before = camera.surface_to_texel(cursor, 480);
+            for factor in [2.0, 1.5, 0.25, 8.0] {
+                camera.zoom_around(cursor, factor, 480, torus_size);
+                let after = camera.surface_to_texel(cursor, 480);
+                match torus_size {
+                    None => assert_near(after, before),
+                    // The offset may wrap, moving the texel by whole tori
+                    Some((width, height)) => assert_near(
+                        (after.0.rem_euclid(width as f64), after.1.rem_euclid(height as f64)),
+                        (before.0.rem_euclid(width as f64), before.1.rem_euclid(height as f64)),
+                    ),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn panning_wraps_around_a_torus() {
+        let mut camera = Camera::default();
+        camera.pan((30.0, 20.0), Some((100, 80)));
+        assert_near(camera.offset, (70.0, 20.0));
+        camera.pan((-250.0, -130.0), Some((100, 80)));
+        assert_near(camera.offset, (20.0, 50.0));
+
+        // An unbounded plane keeps panning
+        let mut camera = Camera::default();
+        camera.pan((30.0, 20.0), None);
+        assert_near(camera.offset, (-30.0, 20.0));
+    }
+
+    #[test]
+    fn copies_cover_the_surface() {
+        let mut camera = Camera::default();
+        assert_eq!(camera.visible_copies((640, 480), None), ((0, 0), (1, 1)));
+        assert_eq!(camera.visible_copies((640, 480), Some((640, 480))), ((0, 0), (2, 2)));
+        assert_eq!(camera.visible_copies((640, 480), Some((1000, 1000))), ((0, 0), (1, 1)));
+        camera.pan((100.0, 0.0), Some((300, 500)));
+        camera.zoom = 0.5;
+        // The offset wrapped to 200, so the surface spans texels 200 to 1480 across
+        assert_eq!(camera.visible_copies((640, 480), Some((300, 500))), ((0, 0), (5, 2)));
+    }
+
+    #[test]
+    fn far_offsets_keep_their_fraction() {
+        let mut camera = Camera::default();
+        camera.pan((-3e7 - 0.25, -2.5), None);
+        let uniform = camera.uniform((640, 480), None);
+        assert_eq!(uniform.offset, [30_000_000, -3]);
+        assert_eq!(uniform.offset_fraction, [0.25, 0.5]);
+    }
+}
@@ -0,0 +1,12 @@
+// Rg32Float cells: r is 1.0 for live cells and -1.0 for dead ones, g is the trail
+@group(0) @binding(0) var input_texture : texture_2d<f32>;
+@group(0) @binding(1) var output_texture : texture_storage_2d<rg32float, write>;
+
+fn load_cell(coords: vec2<u32>) -> vec4<f32> {
+    return textureLoad(input_texture, coords, 0);
+}
+
+fn store_cell(coords: vec2<u32>, cell: vec4<f32>) {
+    textureStore(output_texture, coords, cell);
+}
+
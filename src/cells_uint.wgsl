@@ -0,0 +1,21 @@
+// Integer cells: `ALIVE` is set for live cells and the bits below `TRAIL_MAX` hold the
+// trail, see `TexelLayout` in format.rs. Cells are loaded in the same form as
+// cells_float.wgsl.
+@group(0) @binding(0) var input_texture : texture_2d<u32>;
+@group(0) @binding(1) var output_texture : texture_storage_2d<{format}, write>;
+
+const ALIVE: u32 = {alive}u;
+const TRAIL_MAX: u32 = {trail_max}u;
+
+fn load_cell(coords: vec2<u32>) -> vec4<f32> {
+    let texel = textureLoad(input_texture, coords, 0).r;
+    let state = select(-1.0, 1.0, (texel & ALIVE) != 0u);
+    return vec4<f32>(state, f32(texel & TRAIL_MAX) / f32(TRAIL_MAX), 0.0, 0.0);
+}
+
+fn store_cell(coords: vec2<u32>, cell: vec4<f32>) {
+    // The bias keeps trails from rounding down when they are stored unchanged
+    let trail = u32(clamp(cell.g, 0.0, 1.0) * f32(TRAIL_MAX) + 0.01);
+    let alive = select(0u, ALIVE, cell.r > 0.0);
+    textureStore(output_texture, coords, vec4<u32>(alive | trail, 0u, 0u, 0u));
+}
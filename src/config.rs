@@ -0,0 +1,308 @@
+use std::fmt;
+use std::path::PathBuf;
+use wgpu_automata::{Boundary, CellFormat, Generator, Kernel, Pattern, Rule, SimulationOptions};
+
+pub const USAGE: &str = "\
+Usage: wgpu_automata [OPTIONS]
+
+Options:
+  --size <W>x<H>        Grid size in cells, or a single number for a square grid [default: 1024]
+  --window <W>x<H>      Window size in pixels [default: 1024x1024]
+  --format <FORMAT>     Cell storage: float, r32uint or r8uint [default: float]
+  --kernel <KERNEL>     Transition kernel: texture, or bits for 32 cells per invocation with
+                        a grid width that is a multiple of 32 [default: texture]
+  --skip-stable         Only transition tiles near cells that changed (texture kernel)
+  --block <K>           Generations advanced per dispatch in workgroup memory, less than
+                        half the workgroup size (texture kernel) [default: 1]
+  --workgroup <N>       Edge of the square workgroups of the texture kernel [default: 16]
+  --chunk <N>           Largest side of the textures a grid is split into, for grids larger
+                        than the adapter allows (texture kernel on a torus) [default: limit],
+                        or the side of the chunks of an unbounded universe [default: 256]
+  --unbounded           Simulate an unbounded plane that grows chunks where cells spread
+                        (texture kernel), with the grid size as the initial region
+  --present <MODE>      Present mode: fifo, mailbox, immediate or auto [default: fifo]
+  --rule <RULE>         Rule such as B3/S23, overriding the rule of a pattern file
+  --boundary <BOUNDARY> How the edges are joined: torus, dead, mirror, klein or cross
+                        [default: torus]
+  --seed <SEED>         Seed for the random initial grid
+  --density <DENSITY>   Fraction of live cells in the random initial grid [default: 0.5]
+  --init <GENERATOR>    Initial grid: wedge, soup, square, disc, c2, c4, d4, d8, stripes,
+                        single or noise [default: wedge]
+  --pattern <FILE>      Pattern file (RLE, plaintext or Life 1.05/1.06) placed in the center
+  --headless [N]        Run N generations without a window and print the result [default: 1000]
+  --verify              With --headless, compare the result against the CPU reference
+  --export <FILE>       With --headless, save the final generation as a pattern file
+  --help                Print this message
+";
+
+// Settings chosen on the command line
+#[derive(Clone, Debug, PartialEq)]
+pub struct Config {
+    pub grid_size: (u32, u32),
+    pub window_size: (u32, u32),
+    pub format: CellFormat,
+    pub kernel: Kernel,
+    pub skip_stable_tiles: bool,
+    pub block_generations: u32,
+    pub workgroup_size: u32,
+    pub chunk_size: Option<u32>,
+    pub unbounded: bool,
+    pub present_mode: wgpu::PresentMode,
+    pub rule: Option<Rule>,
+    pub boundary: Boundary,
+    pub seed: Option<u64>,
+    pub density: f64,
+    pub generator: Generator,
+    pub pattern: Option<PathBuf>,
+    pub headless: Option<u32>,
+    pub verify: bool,
+    pub export: Option<PathBuf>,
+    pub help: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            grid_size: (1024, 1024),
+            window_size: (1024, 1024),
+            format: CellFormat::default(),
+            kernel: Kernel::default(),
+            skip_stable_tiles: false,
+            block_generations: 1,
+            workgroup_size: 16,
+            chunk_size: None,
+            unbounded: false,
+            present_mode: wgpu::PresentMode::Fifo,
+            rule: None,
+            boundary: Boundary::default(),
+            seed: None,
+            density: 0.5,
+            generator: Generator::Wedge,
+            pattern: None,
+            headless: None,
+            verify: false,
+            export: None,
+            help: false,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ConfigError {
+    UnknownOption(String),
+    MissingValue(String),
+    InvalidValue { option: String, value: String, message: String },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::UnknownOption(option) => write!(f, "unknown option '{}'", option),
+            ConfigError::MissingValue(option) => write!(f, "missing value for '{}'", option),
+            ConfigError::InvalidValue { option, value, message } => {
+                write!(f, "invalid value '{}' for '{}': {}", value, option, message)
+            },
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    // Parses the arguments following the program name
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Config, ConfigError> {
+        let mut config = Config::default();
+        let mut args = args.into_iter().peekable();
+
+        while let Some(option) = args.next() {
+            let mut value = || args.next().ok_or_else(|| ConfigError::MissingValue(option.clone()));
+            match option.as_str() {
+                "--size" => config.grid_size = parse_size(&option, &value()?)?,
+                "--window" => config.window_size = parse_size(&option, &value()?)?,
+                "--format" => config.format = parse_value(&option, &value()?)?,
+                "--kernel" => config.kernel = parse_value(&option, &value()?)?,
+                "--block" => config.block_generations = parse_positive(&option, &value()?)?,
+                "--workgroup" => {
+                    let value = value()?;
+                    config.workgroup_size = parse_value(&option, &value)?;
+                    if config.workgroup_size < 3 {
+                        return Err(invalid(&option, &value, "must be at least 3"));
+                    }
+                },
+                "--chunk" => {
+                    let value = value()?;
+                    let chunk_size = parse_value(&option, &value)?;
+                    if chunk_size < 3 {
+                        return Err(invalid(&option, &value, "must be at least 3"));
+                    }
+                    config.chunk_size = Some(chunk_size);
+                },
+                "--present" => config.present_mode = parse_present_mode(&option, &value()?)?,
+                "--rule" => config.rule = Some(parse_value(&option, &value()?)?),
+                "--boundary" => config.boundary = parse_value(&option, &value()?)?,
+                "--seed" => config.seed = Some(parse_value(&option, &value()?)?),
+                "--density" => {
+                    let value = value()?;
+                    config.density = parse_value(&option, &value)?;
+                    if !(0.0..=1.0).contains(&config.density) {
+                        return Err(invalid(&option, &value, "must be between 0 and 1"));
+                    }
+                },
+                "--init" => config.generator = parse_value(&option, &value()?)?,
+                "--pattern" => config.pattern = Some(value()?.into()),
+                "--export" => config.export = Some(value()?.into()),
+                "--skip-stable" => config.skip_stable_tiles = true,
+                "--unbounded" => config.unbounded = true,
+                "--verify" => config.verify = true,
+                "--help" | "-h" => config.help = true,
+                "--headless" => {
+                    // The generation count is optional
+                    let generations = args.next_if(|arg| !arg.starts_with("--"));
+                    config.headless = Some(match generations {
+                        Some(generations) => parse_value(&option, &generations)?,
+                        None => 1000,
+                    });
+                },
+                _ => return Err(ConfigError::UnknownOption(option)),
+            }
+        }
+
+        // The block of generations advanced per dispatch must leave a valid interior in
+        // every workgroup
+        if config.block_generations.checked_mul(2).is_none_or(|twice| twice >= config.workgroup_size) {
+            return Err(invalid(
+                "--block",
+                &config.block_generations.to_string(),
+                format!("must be less than half the workgroup size {}", config.workgroup_size),
+            ));
+        }
+
+        Ok(config)
+    }
+
+    // The rule given on the command line takes precedence over the one in the pattern
+    pub fn simulation_options(&self, pattern: Option<&Pattern>) -> SimulationOptions {
+        SimulationOptions {
+            size: self.grid_size,
+            format: self.format,
+            kernel: self.kernel,
+            skip_stable_tiles: self.skip_stable_tiles,
+            block_generations: self.block_generations,
+            workgroup_size: self.workgroup_size,
+            rule: self.rule.or(pattern.and_then(Pattern::rule)).unwrap_or_default(),
+            boundary: self.boundary,
+            ..SimulationOptions::default()
+        }
+    }
+}
+
+fn invalid(option: &str, value: &str, message: impl ToString) -> ConfigError {
+    ConfigError::InvalidValue {
+        option: option.to_string(),
+        value: value.to_string(),
+        message: message.to_string(),
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(option: &str, value: &str) -> Result<T, ConfigError>
+where
+    T::Err: fmt::Display,
+{
+    value.parse().map_err(|err| invalid(option, value, err))
+}
+
+fn parse_positive(option: &str, value: &str) -> Result<u32, ConfigError> {
+    match parse_value(option, value)? {
+        0 => Err(invalid(option, value, "must be positive")),
+        number => Ok(number),
+    }
+}
+
+fn parse_present_mode(option: &str, value: &str) -> Result<wgpu::PresentMode, ConfigError> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "fifo" => Ok(wgpu::PresentMode::Fifo),
+        "mailbox" => Ok(wgpu::PresentMode::Mailbox),
+        "immediate" => Ok(wgpu::PresentMode::Immediate),
+        "auto" => Ok(wgpu::PresentMode::AutoVsync),
+        _ => Err(invalid(option, value, "expected fifo, mailbox, immediate or auto")),
+    }
+}
+
+// Accepts "WxH" or a single number for a square
+fn parse_size(option: &str, value: &str) -> Result<(u32, u32), ConfigError> {
+    let (width, height) = match value.split_once(['x', 'X']) {
+        Some((width, height)) => (parse_value(option, width)?, parse_value(option, height)?),
+        None => {
+            let size = parse_value(option, value)?;
+            (size, size)
+        },
+    };
+    if width == 0 || height == 0 {
+        return Err(invalid(option, value, "dimensions must be positive"));
+    }
+    Ok((width, height))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Config, ConfigError> {
+        Config::from_args(args.split_whitespace().map(str::to_string))
+    }
+
+    fn message(args: &str) -> String {
+        match parse(args) {
+            Err(ConfigError::InvalidValue { message, .. }) => message,
+            result => panic!("expected an invalid value for '{}', got {:?}", args, result),
+        }
+    }
+
+    #[test]
+    fn parses_options() {
+        assert_eq!(parse("").unwrap(), Config::default());
+        let config = parse("--size 300x200 --window 640 --density 0.25 --block 3 --workgroup 8 --chunk 64 --present mailbox").unwrap();
+        assert_eq!(config.grid_size, (300, 200));
+        assert_eq!(config.window_size, (640, 640));
+        assert_eq!(config.density, 0.25);
+        assert_eq!(config.block_generations, 3);
+        assert_eq!(config.workgroup_size, 8);
+        assert_eq!(config.chunk_size, Some(64));
+        assert_eq!(config.present_mode, wgpu::PresentMode::Mailbox);
+
+        assert_eq!(parse("--size 12X34").unwrap().grid_size, (12, 34));
+        assert_eq!(parse("--present AUTO").unwrap().present_mode, wgpu::PresentMode::AutoVsync);
+        assert_eq!(parse("--headless --verify").unwrap().headless, Some(1000));
+        assert_eq!(parse("--headless 20 --verify").unwrap().headless, Some(20));
+        assert_eq!(parse("").unwrap().generator, Generator::Wedge);
+        assert_eq!(parse("--init soup").unwrap().generator, Generator::Soup);
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        assert_eq!(message("--size 0x5"), "dimensions must be positive");
+        assert!(!message("--size 5x").is_empty());
+        assert!(!message("--size -5").is_empty());
+        assert_eq!(message("--density 1.5"), "must be between 0 and 1");
+        assert!(!message("--density half").is_empty());
+        assert_eq!(message("--block 0"), "must be positive");
+        assert_eq!(message("--workgroup 2"), "must be at least 3");
+        assert_eq!(message("--chunk 2"), "must be at least 3");
+        assert_eq!(message("--block 9"), "must be less than half the workgroup size 16");
+        assert_eq!(message("--workgroup 8 --block 4"), "must be less than half the workgroup size 8");
+        assert_eq!(message("--block 4294967295 --workgroup 4294967295"), "must be less than half the workgroup size 4294967295");
+        assert_eq!(message("--present vsync"), "expected fifo, mailbox, immediate or auto");
+    }
+
+    #[test]
+    fn rejects_unknown_and_incomplete_options() {
+        assert_eq!(parse("--sise 5"), Err(ConfigError::UnknownOption("--sise".to_string())));
+        assert_eq!(parse("--size"), Err(ConfigError::MissingValue("--size".to_string())));
+        assert_eq!(parse("--verify --chunk"), Err(ConfigError::MissingValue("--chunk".to_string())));
+        assert_eq!(
+            parse("--present").unwrap_err().to_string(),
+            "missing value for '--present'",
+        );
+    }
+}
@@ -1,6 +1,21 @@
-@group(0) @binding(0) var texture : texture_2d<f32>;
-@group(0) @binding(1) var texture_sampler: sampler;
-@group(1) @binding(0) var<uniform> surface_size: vec2<u32>;
+@group(0) @binding(1) var<uniform> chunk: Chunk;
+@group(1) @binding(0) var<uniform> camera: Camera;
+
+struct Chunk {
+    origin: vec2<i32>,     // position of the chunk interior in the world
+    size: vec2<u32>,       // size of the chunk interior
+    world_size: vec2<u32>, // size of the torus, zero for an unbounded plane
+    halo: u32,             // texels around the interior in the chunk texture
+};
+
+struct Camera {
+    surface_size: vec2<f32>,
+    offset: vec2<i32>,          // texel at the bottom left corner of the surface
+    offset_fraction: vec2<f32>, // position within that texel
+    first_copy: vec2<i32>,      // the lowest copy of a torus on the surface
+    zoom: f32,                  // surface pixels per texel
+    copy_columns: u32,          // copies of a torus drawn per row of instances
+};
 
 struct VertexInput {
     @location(0) position: vec3<f32>,
@@ -8,24 +23,28 @@ struct VertexInput {
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
-    @location(0) uv: vec2<f32>,
+    @location(0) local: vec2<f32>, // texel within the chunk interior
 };
 
+// The quad covers the interior of the chunk, each instance in another copy of a torus
 @vertex
 fn vs_main(
     model: VertexInput,
+    @builtin(instance_index) instance: u32,
 ) -> VertexOutput {
     var out: VertexOutput;
-    let dimensions = vec2<f32>(textureDimensions(texture));
-    let surface_size_f = vec2<f32>(surface_size);
-    out.uv = (model.position.xy * surface_size_f / dimensions);
-    out.clip_position = vec4<f32>(model.position.xy * 2 - 1, 0.0, 1.0);
+    let copy = camera.first_copy + vec2<i32>(vec2<u32>(instance % camera.copy_columns, instance / camera.copy_columns));
+    let origin = chunk.origin + copy * vec2<i32>(chunk.world_size);
+    out.local = model.position.xy * vec2<f32>(chunk.size);
+    let texel = vec2<f32>(origin - camera.offset) - camera.offset_fraction + out.local;
+    out.clip_position = vec4<f32>(texel * camera.zoom / camera.surface_size * 2 - 1, 0.0, 1.0);
     return out;
 }
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    let color = textureSample(texture, texture_sampler, in.uv);
+    let local = min(vec2<u32>(floor(in.local)), chunk.size - 1u);
+    let color = load_cell(local + chunk.halo);
     return max(mix(
         vec4<f32>(0.0, -0.4, 0.0, 1.0),
         vec4<f32>(0.0, 1.2, 0.5, 1.0),
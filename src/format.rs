@@ -0,0 +1,174 @@
+//! How cells are stored in the simulation textures.
+
+use std::fmt;
+use std::str::FromStr;
+use crate::grid::Cell;
+
+// How an integer texel holds a cell: `alive` is set for live cells and the low bits
+// hold the trail quantized to `trail_max`
+struct TexelLayout {
+    alive: u32,
+    trail_max: u32,
+}
+
+const UINT8_LAYOUT: TexelLayout = TexelLayout { alive: 0x80, trail_max: 0x7f };
+// A longer trail would lose precision in the f32 math of the shaders
+const UINT32_LAYOUT: TexelLayout = TexelLayout { alive: 0x8000_0000, trail_max: 0xffff };
+
+/// How cells are stored in the simulation textures. The shaders and the CPU side see
+/// every format as `Cell`s, the integer formats pack the state and a quantized trail
+/// into the texel.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum CellFormat {
+    /// Rg32Float, 8 bytes per cell
+    #[default]
+    Float,
+    /// R32Uint, 4 bytes per cell with a 16 bit trail
+    Uint32,
+    /// R8Uint, 1 byte per cell with a 7 bit trail. Only some adapters can write it
+    /// from shaders.
+    Uint8,
+}
+
+impl CellFormat {
+    /// Texel format of the cell textures
+    pub fn texture_format(self) -> wgpu::TextureFormat {
+        match self {
+            CellFormat::Float => wgpu::TextureFormat::Rg32Float,
+            CellFormat::Uint32 => wgpu::TextureFormat::R32Uint,
+            CellFormat::Uint8 => wgpu::TextureFormat::R8Uint,
+        }
+    }
+
+    /// How shaders sample the cell textures
+    pub fn sample_type(self) -> wgpu::TextureSampleType {
+        match self {
+            CellFormat::Float => wgpu::TextureSampleType::Float { filterable: false },
+            CellFormat::Uint32 | CellFormat::Uint8 => wgpu::TextureSampleType::Uint,
+        }
+    }
+
+    /// Size of one texel, see `encode`
+    pub fn bytes_per_cell(self) -> u32 {
+        match self {
+            CellFormat::Float => std::mem::size_of::<Cell>() as u32,
+            CellFormat::Uint32 => 4,
+            CellFormat::Uint8 => 1,
+        }
+    }
+
+    /// Prepends the declarations of `input_texture`, `output_texture`, `load_cell` and
+    /// `store_cell` for this format to a shader
+    pub fn shader_source(self, shader: &str) -> String {
+        let prelude = match self {
+            CellFormat::Float => include_str!("cells_float.wgsl").to_string(),
+            CellFormat::Uint32 => UINT32_LAYOUT.shader_source("r32uint"),
+            CellFormat::Uint8 => UINT8_LAYOUT.shader_source("r8uint"),
+        };
+        prelude + shader
+    }
+
+    /// Converts cells to texel data
+    pub fn encode(self, cells: &[Cell]) -> Vec<u8> {
+        match self {
+            CellFormat::Float => bytemuck::cast_slice(cells).to_vec(),
+            CellFormat::Uint32 => cells.iter().flat_map(|&cell| UINT32_LAYOUT.encode(cell).to_le_bytes()).collect(),
+            CellFormat::Uint8 => cells.iter().map(|&cell| UINT8_LAYOUT.encode(cell) as u8).collect(),
+        }
+    }
+
+    /// Converts texel data back to cells
+    pub fn decode(self, data: &[u8]) -> Vec<Cell> {
+        match self {
+            CellFormat::Float => data.chunks_exact(std::mem::size_of::<Cell>()).map(bytemuck::pod_read_unaligned).collect(),
+            CellFormat::Uint32 => data
+                .chunks_exact(4)
+                .map(|texel| UINT32_LAYOUT.decode(u32::from_le_bytes(texel.try_into().unwrap())))
+                .collect(),
+            CellFormat::Uint8 => data.iter().map(|&texel| UINT8_LAYOUT.decode(texel as u32)).collect(),
+        }
+    }
+}
+
+impl TexelLayout {
+    fn shader_source(&self, format: &str) -> String {
+        include_str!("cells_uint.wgsl")
+            .replace("{format}", format)
+            .replace("{alive}", &self.alive.to_string())
+            .replace("{trail_max}", &self.trail_max.to_string())
+    }
+
+    // Mirrors `store_cell` in cells_uint.wgsl
+    fn encode(&self, cell: Cell) -> u32 {
+        let trail = (cell.trail.clamp(0.0, 1.0) * self.trail_max as f32 + 0.01) as u32;
+        if cell.is_alive() { self.alive | trail } else { trail }
+    }
+
+    // Mirrors `load_cell` in cells_uint.wgsl
+    fn decode(&self, texel: u32) -> Cell {
+        Cell {
+            state: if texel & self.alive != 0 { 1.0 } else { -1.0 },
+            trail: (texel & self.trail_max) as f32 / self.trail_max as f32,
+        }
+    }
+}
+
+impl fmt::Display for CellFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            CellFormat::Float => "float",
+            CellFormat::Uint32 => "r32uint",
+            CellFormat::Uint8 => "r8uint",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+/// A format name that is not one of float, r32uint or r8uint
+pub struct ParseCellFormatError(String);
+
+impl fmt::Display for ParseCellFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown cell format '{}', expected float, r32uint or r8uint", self.0)
+    }
+}
+
+impl std::error::Error for ParseCellFormatError {}
+
+impl FromStr for CellFormat {
+    type Err = ParseCellFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "float" | "rg32float" => Ok(CellFormat::Float),
+            "r32uint" => Ok(CellFormat::Uint32),
+            "r8uint" => Ok(CellFormat::Uint8),
+            _ => Err(ParseCellFormatError(s.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integer_formats_round_trip() {
+        for (format, layout) in [(CellFormat::Uint32, UINT32_LAYOUT), (CellFormat::Uint8, UINT8_LAYOUT)] {
+            let trail_max = layout.trail_max as f32;
+            let cells: Vec<Cell> = (0..=layout.trail_max)
+                .map(|trail| Cell { state: if trail % 3 == 0 { 1.0 } else { -1.0 }, trail: trail as f32 / trail_max })
+                .chain([Cell::ALIVE, Cell::DEAD, Cell::default(), Cell { state: 1.0, trail: 0.3 }])
+                .collect();
+            let data = format.encode(&cells);
+            assert_eq!(data.len(), cells.len() * format.bytes_per_cell() as usize);
+            let decoded = format.decode(&data);
+            assert_eq!(format.encode(&decoded), data);
+            for (cell, decoded) in cells.iter().zip(&decoded) {
+                assert_eq!(cell.is_alive(), decoded.is_alive());
+                assert!((cell.trail - decoded.trail).abs() < 1.0 / trail_max);
+            }
+        }
+    }
+}
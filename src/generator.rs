@@ -0,0 +1,277 @@
+//! Deterministic initial grids.
+
+use std::fmt;
+use std::str::FromStr;
+use crate::grid::{Cell, Grid};
+use crate::random::{self, CellRng};
+
+// Width of the bands drawn by `Generator::Stripes`
+const STRIPE_WIDTH: u32 = 8;
+// Spacing of the coarsest lattice used by `Generator::Noise`
+const NOISE_SCALE: u32 = 64;
+const NOISE_OCTAVES: u32 = 3;
+
+/// Symmetry groups of the square, named as in Catagolue
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Symmetry {
+    /// 180 degree rotation
+    C2,
+    /// 90 degree rotation
+    C4,
+    /// Horizontal and vertical mirrors
+    D4,
+    /// All rotations and mirrors
+    D8,
+}
+
+impl Symmetry {
+    // The images of a cell of an n by n square under the group
+    fn orbit(self, (x, y): (u32, u32), n: u32) -> Vec<(u32, u32)> {
+        let (fx, fy) = (n - 1 - x, n - 1 - y);
+        match self {
+            Symmetry::C2 => vec![(x, y), (fx, fy)],
+            Symmetry::C4 => vec![(x, y), (fy, x), (fx, fy), (y, fx)],
+            Symmetry::D4 => vec![(x, y), (fx, y), (x, fy), (fx, fy)],
+            Symmetry::D8 => vec![(x, y), (fy, x), (fx, fy), (y, fx), (fx, y), (x, fy), (y, x), (fy, fx)],
+        }
+    }
+}
+
+/// Ways of filling the grid before a run
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Generator {
+    /// Random cells in a wedge around the center, mirrored four ways
+    Wedge,
+    /// Random cells everywhere, generated on the GPU by `Simulation::randomize`
+    Soup,
+    /// Random cells in a centered square half the size of the grid
+    Square,
+    /// Random cells in a centered disc half the size of the grid
+    Disc,
+    /// A centered square soup with the given symmetry
+    Symmetric(Symmetry),
+    /// Alternating bands of soup and dead cells
+    Stripes,
+    /// One live cell in the center
+    SingleCell,
+    /// Live cells where smooth value noise falls below the density
+    Noise,
+}
+
+impl Generator {
+    /// Generators cycled through at runtime
+    pub const ALL: &'static [Generator] = &[
+        Generator::Wedge,
+        Generator::Soup,
+        Generator::Square,
+        Generator::Disc,
+        Generator::Symmetric(Symmetry::C2),
+        Generator::Symmetric(Symmetry::C4),
+        Generator::Symmetric(Symmetry::D4),
+        Generator::Symmetric(Symmetry::D8),
+        Generator::Stripes,
+        Generator::SingleCell,
+        Generator::Noise,
+    ];
+
+    /// Builds a grid where `density` is the chance of a random cell being alive. The same
+    /// seed and density always produce the same grid.
+    pub fn generate(self, (width, height): (u32, u32), seed: u64, density: f64) -> Grid {
+        let mut rng = CellRng::new(seed);
+        let mut grid = Grid::from_cells(width, height, vec![Cell::DEAD; (width * height) as usize]);
+        let set = |grid: &mut Grid, x: u32, y: u32, alive: bool| {
+            grid.set(x, y, if alive { Cell::ALIVE } else { Cell::DEAD });
+        };
+
+        // Centered square of half the smaller side, used by the soup shapes
+        let side = width.min(height) / 2;
+        let corner = ((width - side) / 2, (height - side) / 2);
+
+        match self {
+            Generator::Wedge => {
+                let (half_width, half_height) = (width as i64 / 2, height as i64 / 2);
+                for y in 0..height / 2 {
+                    for x in 0..width / 2 {
+                        let alive = rng.next_alive(density);
+                        let (x, y) = (x as i64, y as i64);
+                        if x <= half_width - y || x >= width as i64 / 4 + (half_height - y) {
+                            continue;
+                        }
+
+                        let (x, y) = (x as u32, y as u32);
+                        set(&mut grid, x, y, alive);
+                        set(&mut grid, width - x - 1, y, alive);
+                        set(&mut grid, x, height - y - 1, alive);
+                        set(&mut grid, width - x - 1, height - y - 1, alive);
+                    }
+                }
+            },
+            Generator::Soup => {
+                for y in 0..height {
+                    for x in 0..width {
+                        set(&mut grid, x, y, random::soup_cell(seed, (x, y), density));
+                    }
+                }
+            },
+            Generator::Square => {
+                for y in 0..side {
+                    for x in 0..side {
+                        set(&mut grid, corner.0 + x, corner.1 + y, rng.next_alive(density));
+                    }
+                }
+            },
+            Generator::Disc => {
+                let radius = side as f64 / 2.0;
+                for y in 0..side {
+                    for x in 0..side {
+                        let alive = rng.next_alive(density);
+                        let (dx, dy) = (x as f64 + 0.5 - radius, y as f64 + 0.5 - radius);
+                        if dx * dx + dy * dy <= radius * radius {
+                            set(&mut grid, corner.0 + x, corner.1 + y, alive);
+                        }
+                    }
+                }
+            },
+            Generator::Symmetric(symmetry) => {
+                // Every cell copies the first cell of its orbit in row order
+                let random: Vec<bool> = (0..side * side).map(|_| rng.next_alive(density)).collect();
+                for y in 0..side {
+                    for x in 0..side {
+                        let (ox, oy) = symmetry.orbit((x, y), side).into_iter().min_by_key(|&(x, y)| (y, x)).unwrap();
+                        set(&mut grid, corner.0 + x, corner.1 + y, random[(oy * side + ox) as usize]);
+                    }
+                }
+            },
+            Generator::Stripes => {
+                for y in 0..height {
+                    for x in 0..width {
+                        let alive = rng.next_alive(density);
+                        if (x / STRIPE_WIDTH).is_multiple_of(2) {
+                            set(&mut grid, x, y, alive);
+                        }
+                    }
+                }
+            },
+            Generator::SingleCell => set(&mut grid, width / 2, height / 2, true),
+            Generator::Noise => {
+                let noise = ValueNoise::new(&mut rng, (width, height));
+                for y in 0..height {
+                    for x in 0..width {
+                        set(&mut grid, x, y, noise.sample(x, y) < density);
+                    }
+                }
+            },
+        }
+        grid
+    }
+}
+
+impl fmt::Display for Generator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Generator::Wedge => "wedge",
+            Generator::Soup => "soup",
+            Generator::Square => "square",
+            Generator::Disc => "disc",
+            Generator::Symmetric(Symmetry::C2) => "c2",
+            Generator::Symmetric(Symmetry::C4) => "c4",
+            Generator::Symmetric(Symmetry::D4) => "d4",
+            Generator::Symmetric(Symmetry::D8) => "d8",
+            Generator::Stripes => "stripes",
+            Generator::SingleCell => "single",
+            Generator::Noise => "noise",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+/// A generator name that is not one of the `Generator` variants
+pub struct ParseGeneratorError(String);
+
+impl fmt::Display for ParseGeneratorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<String> = Generator::ALL.iter().map(Generator::to_string).collect();
+        write!(f, "unknown generator '{}', expected one of {}", self.0, names.join(", "))
+    }
+}
+
+impl std::error::Error for ParseGeneratorError {}
+
+impl FromStr for Generator {
+    type Err = ParseGeneratorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_ascii_lowercase();
+        Generator::ALL.iter()
+            .copied()
+            .find(|generator| generator.to_string() == name)
+            .ok_or_else(|| ParseGeneratorError(s.to_string()))
+    }
+}
+
+// Octaves of smoothly interpolated random lattice values. The lattices wrap around the
+// grid so the noise is seamless on the torus.
+struct ValueNoise {
+    octaves: Vec<(u32, u32, u32, Vec<f64>)>,
+}
+
+impl ValueNoise {
+    fn new(rng: &mut CellRng, (width, height): (u32, u32)) -> ValueNoise {
+        let octaves = (0..NOISE_OCTAVES)
+            .map(|octave| {
+                let spacing = (NOISE_SCALE >> octave).max(1);
+                let (columns, rows) = (width.div_ceil(spacing), height.div_ceil(spacing));
+                let values = (0..columns * rows).map(|_| rng.next_unit()).collect();
+                (spacing, columns, rows, values)
+            })
+            .collect();
+        ValueNoise { octaves }
+    }
+
+    // A value in [0, 1) that varies smoothly over the grid
+    fn sample(&self, x: u32, y: u32) -> f64 {
+        let mut total = 0.0;
+        let mut weight_sum = 0.0;
+        for (octave, (spacing, columns, rows, values)) in self.octaves.iter().enumerate() {
+            let lattice = |column: u32, row: u32| values[((row % rows) * columns + column % columns) as usize];
+            let (column, row) = (x / spacing, y / spacing);
+            let smooth = |t: f64| t * t * (3.0 - 2.0 * t);
+            let tx = smooth((x % spacing) as f64 / *spacing as f64);
+            let ty = smooth((y % spacing) as f64 / *spacing as f64);
+
+            let top = lattice(column, row) * (1.0 - tx) + lattice(column + 1, row) * tx;
+            let bottom = lattice(column, row + 1) * (1.0 - tx) + lattice(column + 1, row + 1) * tx;
+            let weight = 0.5f64.powi(octave as i32);
+            total += (top * (1.0 - ty) + bottom * ty) * weight;
+            weight_sum += weight;
+        }
+        total / weight_sum
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn symmetric_generators_are_symmetric() {
+        // An odd side puts the center of symmetry on a cell, an even one between cells
+        for size in [(40, 30), (31, 44)] {
+            for symmetry in [Symmetry::C2, Symmetry::C4, Symmetry::D4, Symmetry::D8] {
+                let grid = Generator::Symmetric(symmetry).generate(size, 5, 0.5);
+                let side = size.0.min(size.1) / 2;
+                let corner = ((size.0 - side) / 2, (size.1 - side) / 2);
+                let alive = |(x, y): (u32, u32)| grid.get(corner.0 + x, corner.1 + y).is_alive();
+                for y in 0..side {
+                    for x in 0..side {
+                        for image in symmetry.orbit((x, y), side) {
+                            assert_eq!(alive((x, y)), alive(image), "{:?} at ({}, {})", symmetry, x, y);
+                        }
+                    }
+                }
+                assert!(grid.population() > 0);
+            }
+        }
+    }
+}
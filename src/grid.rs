@@ -0,0 +1,138 @@
+//! Grids of cells on the CPU, as uploaded to and read back from the GPU.
+
+/// A single texel of the simulation textures. `state` is 1.0 for live cells and -1.0 for
+/// dead ones (0.0 for cells that were never written), `trail` is the fading value shown
+/// by the display shader.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Cell {
+    /// 1.0 for live cells, negative or zero for dead ones
+    pub state: f32,
+    /// Brightness of the trail, from 0.0 to 1.0
+    pub trail: f32,
+}
+
+impl Cell {
+    /// A live cell with a full trail
+    pub const ALIVE: Cell = Cell { state: 1.0, trail: 1.0 };
+    /// A dead cell without a trail
+    pub const DEAD: Cell = Cell { state: -1.0, trail: 0.0 };
+
+    /// Whether the cell is alive, ignoring the trail
+    pub fn is_alive(&self) -> bool {
+        self.state > 0.0
+    }
+}
+
+/// Where the cells of a grid end up when it is resized. Cells that do not fit are
+/// dropped and new cells are left unwritten.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Anchor {
+    /// The old grid stays in the middle of the new one
+    #[default]
+    Center,
+    /// The old grid stays in the top left corner
+    Origin,
+    /// The old grid is repeated across the new one from the top left corner, so a grid
+    /// that grows by whole multiples still wraps around the torus the same way
+    Tiled,
+}
+
+impl Anchor {
+    /// Position of the old top left corner in a grid resized from `old` to `new`, which
+    /// is negative where the grid shrinks. Tiled grids start at the origin.
+    pub fn offset(self, (old_width, old_height): (u32, u32), (new_width, new_height): (u32, u32)) -> (i64, i64) {
+        match self {
+            Anchor::Center => (
+                (new_width as i64 - old_width as i64) / 2,
+                (new_height as i64 - old_height as i64) / 2,
+            ),
+            Anchor::Origin | Anchor::Tiled => (0, 0),
+        }
+    }
+}
+
+/// A CPU-side copy of the cell state, stored row by row like the textures
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grid {
+    width: u32,
+    height: u32,
+    cells: Vec<Cell>,
+}
+
+impl Grid {
+    /// A grid of cells that were never written, which count as dead
+    pub fn new(width: u32, height: u32) -> Grid {
+        Grid {
+            width,
+            height,
+            cells: vec![Cell::default(); (width * height) as usize],
+        }
+    }
+
+    /// A grid of the given cells row by row, which must match its size
+    pub fn from_cells(width: u32, height: u32, cells: Vec<Cell>) -> Grid {
+        assert_eq!(cells.len(), (width * height) as usize, "cell count does not match grid size");
+        Grid { width, height, cells }
+    }
+
+    /// Width in cells
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Height in cells
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The cells row by row
+    pub fn cells(&self) -> &[Cell] {
+        &self.cells
+    }
+
+    /// The cell at column `x` of row `y`
+    pub fn get(&self, x: u32, y: u32) -> Cell {
+        self.cells[(y * self.width + x) as usize]
+    }
+
+    /// Overwrites the cell at column `x` of row `y`
+    pub fn set(&mut self, x: u32, y: u32, cell: Cell) {
+        self.cells[(y * self.width + x) as usize] = cell;
+    }
+
+    /// Number of live cells
+    pub fn population(&self) -> usize {
+        self.cells.iter().filter(|cell| cell.is_alive()).count()
+    }
+
+    /// Copies the rectangle at `origin`, which must lie inside the grid
+    pub fn crop(&self, (x, y): (u32, u32), (width, height): (u32, u32)) -> Grid {
+        assert!(x + width <= self.width && y + height <= self.height, "cropped region is outside the grid");
+        let cells = (y..y + height)
+            .flat_map(|row| {
+                let start = (row * self.width + x) as usize;
+                self.cells[start..start + width as usize].iter().copied()
+            })
+            .collect();
+        Grid::from_cells(width, height, cells)
+    }
+
+    /// Copies the grid into a grid of a new size, see `Anchor`
+    pub fn resized(&self, (width, height): (u32, u32), anchor: Anchor) -> Grid {
+        let (offset_x, offset_y) = anchor.offset((self.width, self.height), (width, height));
+        let mut grid = Grid::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let source = match anchor {
+                    Anchor::Tiled => (x as i64 % self.width as i64, y as i64 % self.height as i64),
+                    _ => (x as i64 - offset_x, y as i64 - offset_y),
+                };
+                if (0..self.width as i64).contains(&source.0) && (0..self.height as i64).contains(&source.1) {
+                    grid.set(x, y, self.get(source.0 as u32, source.1 as u32));
+                }
+            }
+        }
+        grid
+    }
+}
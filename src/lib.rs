@@ -0,0 +1,57 @@
+#![warn(missing_docs)]
+//! Life-like cellular automata on the GPU with wgpu.
+//!
+//! A [`Simulation`] owns a device and a pair of cell textures and advances them with
+//! compute shaders, without needing a window:
+//!
+//! ```no_run
+//! use wgpu_automata::{Cell, Generator, Rule, Simulation, SimulationOptions};
+//!
+//! let mut simulation = pollster::block_on(Simulation::new(&SimulationOptions {
+//!     size: (256, 256),
+//!     rule: "B36/S23".parse().unwrap(),
+//!     ..SimulationOptions::default()
+//! }))?;
+//! simulation.randomize(Generator::Soup, 42, 0.5);
+//! simulation.set_cell((10, 10), Cell::ALIVE);
+//! simulation.step(100);
+//! // The copy is mapped while the device is polled
+//! let cells = simulation.read_cells();
+//! simulation.device().poll(wgpu::Maintain::Wait);
+//! let grid = pollster::block_on(cells).expect("the device was lost");
+//! println!("{} cells alive after {} generations", grid.population(), simulation.generation());
+//! # let _ = Rule::CONWAY;
+//! # Ok::<(), wgpu_automata::SimulationError>(())
+//! ```
+//!
+//! Renderers can draw the textures of a simulation directly, see
+//! [`Simulation::texture_view`] and [`Simulation::transition`]. The [`reference`] module
+//! holds a CPU implementation with the same semantics for checking results.
+
+mod activity;
+mod bits;
+mod bounds;
+pub mod boundary;
+pub mod format;
+pub mod generator;
+pub mod grid;
+pub mod pattern;
+mod random;
+pub mod reference;
+pub mod rule;
+pub mod simulation;
+#[cfg(test)]
+mod testing;
+pub mod universe;
+pub mod world;
+
+pub use boundary::Boundary;
+pub use format::CellFormat;
+pub use generator::Generator;
+pub use grid::{Anchor, Cell, Grid};
+pub use pattern::Pattern;
+pub use random::random_seed;
+pub use rule::Rule;
+pub use simulation::{Kernel, ReadCells, Simulation, SimulationError, SimulationOptions};
+pub use universe::Universe;
+pub use world::World;
@@ -1,15 +1,150 @@
 mod app;
+mod camera;
+mod config;
+mod playback;
 mod render;
+mod space;
 
 use crate::app::App;
+use crate::config::{Config, USAGE};
+use crate::space::Space;
+use wgpu_automata::{reference, Anchor, Boundary, Pattern};
+use std::time::Instant;
 use winit::event_loop::{ControlFlow, EventLoop};
 
 fn main() {
     env_logger::init();
 
+    let config = match Config::from_args(std::env::args().skip(1)) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("error: {}\n\n{}", err, USAGE);
+            std::process::exit(2);
+        },
+    };
+    if config.help {
+        print!("{}", USAGE);
+        return;
+    }
+
+    if let Some(generations) = config.headless {
+        run_headless(&config, generations);
+        return;
+    }
+
     let event_loop = EventLoop::new().unwrap();
     event_loop.set_control_flow(ControlFlow::Poll);
 
-    let mut app = App::default();
+    let mut app = App::new(config);
     let _ = event_loop.run_app(&mut app);
 }
+
+fn run_headless(config: &Config, generations: u32) {
+    let pattern = match config.pattern.as_ref().map(Pattern::load).transpose() {
+        Ok(pattern) => pattern,
+        Err(err) => {
+            eprintln!("error: {}: {}", config.pattern.as_ref().unwrap().display(), err);
+            std::process::exit(1);
+        },
+    };
+    let options = config.simulation_options(pattern.as_ref());
+
+    let instance = wgpu::Instance::default();
+    let space = Space::with_instance(&instance, None, &options, config.chunk_size, config.unbounded);
+    let mut space = match pollster::block_on(space) {
+        Ok(space) => space,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            std::process::exit(1);
+        },
+    };
+    println!("Using device {}", space.adapter().get_info().name);
+    let placed = match &pattern {
+        Some(pattern) => {
+            println!(
+                "Loaded pattern {} ({}x{})",
+                pattern.name().map_or_else(|| config.pattern.as_ref().unwrap().display().to_string(), str::to_string),
+                pattern.width(),
+                pattern.height(),
+            );
+            space.center_pattern(pattern)
+        },
+        None => {
+            let seed = config.seed.unwrap_or_else(wgpu_automata::random_seed);
+            println!("Generating {} with seed {} and density {}", config.generator, seed, config.density);
+            space.randomize(config.generator, seed, config.density)
+        },
+    };
+    if let Err(err) = placed {
+        eprintln!("error: {}", err);
+        std::process::exit(1);
+    }
+    let initial = config.verify.then(|| read_back(space.read_cells()));
+    let start = Instant::now();
+    if let Err(err) = space.step(generations) {
+        eprintln!("error: {}", err);
+        std::process::exit(1);
+    }
+    space.device().poll(wgpu::Maintain::Wait);
+    let elapsed = start.elapsed();
+    if let Some(err) = space.take_error() {
+        eprintln!("error: {}", err);
+        std::process::exit(1);
+    }
+
+    let (origin, grid) = read_back(space.read_cells());
+    let described = match &space {
+        Space::World(_) => format!("a {}x{} grid", grid.width(), grid.height()),
+        Space::Universe(universe) => format!(
+            "an unbounded universe in {} chunks, live cells within {}x{} at ({}, {})",
+            universe.chunks().count(),
+            grid.width(),
+            grid.height(),
+            origin.0,
+            origin.1,
+        ),
+    };
+    println!(
+        "Simulated {} generations of {} in {:.3}s ({:.1} generations/s), population {}",
+        space.generation(),
+        described,
+        elapsed.as_secs_f64(),
+        space.generation() as f64 / elapsed.as_secs_f64(),
+        grid.population(),
+    );
+
+    // Compare against the CPU reference implementation. Cells spread by at most one cell
+    // per generation, so a universe matches a dead boundary that far around its cells.
+    if let Some((initial_origin, initial)) = initial {
+        let (expected, actual) = match &space {
+            Space::World(_) => (reference::step_n(&initial, options.rule, options.boundary, generations), grid),
+            Space::Universe(universe) => {
+                let padded = initial.resized((initial.width() + 2 * generations, initial.height() + 2 * generations), Anchor::Center);
+                let origin = (initial_origin.0 - generations as i64, initial_origin.1 - generations as i64);
+                let actual = read_back(universe.read_region(origin, (padded.width(), padded.height())));
+                (reference::step_n(&padded, options.rule, Boundary::Dead, generations), actual)
+            },
+        };
+        match reference::first_difference(&expected, &actual) {
+            None => println!("GPU result matches the CPU reference"),
+            Some((x, y)) => println!("GPU result differs from the CPU reference at ({}, {})", x, y),
+        }
+    }
+
+    if let Some(path) = &config.export {
+        let pattern = read_back(space.export_pattern());
+        if let Err(err) = pattern.save(path) {
+            eprintln!("error: {}: {}", path.display(), err);
+            std::process::exit(1);
+        }
+        println!("Exported generation {} to {}", space.generation(), path.display());
+    }
+}
+
+// Exits when the cells could not be read back from the GPU
+fn read_back<T>(result: Result<T, wgpu::BufferAsyncError>) -> T {
+    result.unwrap_or_else(|err| {
+        eprintln!("error: failed to read back the cells: {}", err);
+        std::process::exit(1);
+    })
+}
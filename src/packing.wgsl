@@ -0,0 +1,37 @@
+// Converts between the cell textures and the words used by bits.wgsl. Rows hold
+// textureDimensions(input_texture).x / 32 words.
+@group(1) @binding(0) var<storage, read_write> words: array<u32>;
+
+@compute
+@workgroup_size(8, 8)
+fn pack(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let dimensions = textureDimensions(input_texture);
+    let words_per_row = dimensions.x / 32u;
+    if global_id.x >= words_per_row || global_id.y >= dimensions.y {
+        return;
+    }
+
+    var word = 0u;
+    for (var i = 0u; i < 32u; i++) {
+        if load_cell(vec2<u32>(global_id.x * 32u + i, global_id.y)).r > 0.0 {
+            word |= 1u << i;
+        }
+    }
+    words[global_id.y * words_per_row + global_id.x] = word;
+}
+
+// Writes the words to the output texture, fading the trail of the input texture once
+// per unpack rather than once per generation
+@compute
+@workgroup_size(16, 16)
+fn unpack(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let dimensions = textureDimensions(input_texture);
+    if global_id.x >= dimensions.x || global_id.y >= dimensions.y {
+        return;
+    }
+
+    let word = words[global_id.y * (dimensions.x / 32u) + global_id.x / 32u];
+    let state = select(-1.0, 1.0, ((word >> (global_id.x % 32u)) & 1u) != 0u);
+    let previous = load_cell(global_id.xy);
+    store_cell(global_id.xy, vec4<f32>(state, max(state, previous.g * 0.99), 0.0, 0.0));
+}
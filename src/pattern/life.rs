@@ -0,0 +1,168 @@
+use crate::pattern::{Pattern, PatternError};
+use crate::rule::Rule;
+
+impl Pattern {
+    /// Parses a Life 1.05 pattern: `#D` description lines, `#N` or `#R` rule lines and
+    /// `#P x y` blocks of `.` and `*` rows positioned relative to the pattern center.
+    pub fn from_life105(text: &str) -> Result<Pattern, PatternError> {
+        let mut comments = Vec::new();
+        let mut rule = None;
+        let mut coordinates = Vec::new();
+        let (mut block_x, mut block_y, mut row) = (0i64, 0i64, 0i64);
+
+        for (index, line) in text.lines().enumerate() {
+            let error = |message: String| PatternError::Parse { line: index + 1, message };
+            let line = line.trim();
+
+            if let Some(comment) = line.strip_prefix("#D") {
+                comments.push(comment.trim().to_string());
+            } else if line.starts_with("#N") {
+                rule = Some(Rule::CONWAY);
+            } else if let Some(value) = line.strip_prefix("#R") {
+                rule = Some(value.trim().parse().map_err(|err| error(format!("invalid rule: {}", err)))?);
+            } else if let Some(position) = line.strip_prefix("#P") {
+                let mut values = position.split_whitespace().map(|value| value.parse::<i64>());
+                match (values.next(), values.next()) {
+                    (Some(Ok(x)), Some(Ok(y))) => (block_x, block_y, row) = (x, y, 0),
+                    _ => return Err(error(format!("invalid block position '{}'", position.trim()))),
+                }
+            } else if !line.starts_with('#') {
+                for (column, c) in line.chars().enumerate() {
+                    match c {
+                        '.' => {},
+                        '*' => coordinates.push((block_x + column as i64, block_y + row)),
+                        _ => return Err(error(format!("unexpected character '{}'", c))),
+                    }
+                }
+                row += 1;
+            }
+        }
+
+        let mut pattern = Pattern::from_coordinates(&coordinates)?;
+        pattern.rule = rule;
+        pattern.comments = comments;
+        Ok(pattern)
+    }
+
+    /// Writes the pattern as a single Life 1.05 block centered on the origin
+    pub fn to_life105(&self) -> String {
+        let mut text = String::from("#Life 1.05\n");
+        for comment in self.name.iter().chain(&self.comments) {
+            text += &format!("#D {}\n", comment);
+        }
+
+        // Life 1.05 writes rules in survival/birth order
+        match self.rule {
+            Some(rule) if rule != Rule::CONWAY => {
+                let survival: String = (0..=8).filter(|&n| rule.survives(n)).map(|n| n.to_string()).collect();
+                let birth: String = (0..=8).filter(|&n| rule.is_born(n)).map(|n| n.to_string()).collect();
+                text += &format!("#R {}/{}\n", survival, birth);
+            },
+            _ => text += "#N\n",
+        }
+
+        // A single block centered on the origin, with trailing dead cells trimmed
+        text += &format!("#P {} {}\n", -(self.width as i64 / 2), -(self.height as i64 / 2));
+        for y in 0..self.height {
+            let row: String = (0..self.width).map(|x| if self.get(x, y) { '*' } else { '.' }).collect();
+            let row = row.trim_end_matches('.');
+            text += if row.is_empty() { "." } else { row };
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Parses a Life 1.06 pattern, a `#Life 1.06` header followed by one `x y` line per
+    /// live cell
+    pub fn from_life106(text: &str) -> Result<Pattern, PatternError> {
+        let mut coordinates = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut values = line.split_whitespace().map(|value| value.parse::<i64>());
+            match (values.next(), values.next(), values.next()) {
+                (Some(Ok(x)), Some(Ok(y)), None) => coordinates.push((x, y)),
+                _ => return Err(PatternError::Parse { line: index + 1, message: format!("invalid cell '{}'", line) }),
+            }
+        }
+
+        Pattern::from_coordinates(&coordinates)
+    }
+
+    /// Writes the coordinates of the live cells as a Life 1.06 pattern
+    pub fn to_life106(&self) -> String {
+        let mut text = String::from("#Life 1.06\n");
+        for (x, y) in self.coordinates() {
+            text += &format!("{} {}\n", x, y);
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pattern::{Pattern, PatternError};
+    use crate::rule::Rule;
+
+    fn glider() -> Pattern {
+        Pattern::from_coordinates(&[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]).unwrap()
+    }
+
+    #[test]
+    fn life105_round_trips() {
+        let mut pattern = glider();
+        pattern.set_rule(Some("B36/S23".parse().unwrap()));
+        let text = pattern.to_life105();
+        assert!(text.contains("#R 23/36\n#P -1 -1\n"));
+        assert_eq!(Pattern::from_life105(&text).unwrap(), pattern);
+
+        pattern.set_rule(Some(Rule::CONWAY));
+        assert!(pattern.to_life105().contains("#N\n"));
+        assert_eq!(Pattern::from_life105(&pattern.to_life105()).unwrap(), pattern);
+    }
+
+    #[test]
+    fn life105_joins_blocks() {
+        let text = "#Life 1.05\n#D Two blocks\n#P -5 0\n**\n#P 5 1\n.*\n";
+        let pattern = Pattern::from_life105(text).unwrap();
+        assert_eq!((pattern.width(), pattern.height()), (12, 2));
+        assert_eq!(pattern.coordinates(), vec![(0, 0), (1, 0), (11, 1)]);
+    }
+
+    #[test]
+    fn life106_round_trips() {
+        let pattern = glider();
+        let text = pattern.to_life106();
+        assert_eq!(text, "#Life 1.06\n1 0\n2 1\n0 2\n1 2\n2 2\n");
+        assert_eq!(Pattern::from_life106(&text).unwrap(), pattern);
+
+        // Negative coordinates are moved to the top left corner
+        let pattern = Pattern::from_life106("#Life 1.06\n-3 -1\n-2 -1\n\n-3 0\n").unwrap();
+        assert_eq!(pattern.coordinates(), vec![(0, 0), (1, 0), (0, 1)]);
+        assert_eq!(Pattern::from_life106("#Life 1.06\n").unwrap(), Pattern::new(0, 0));
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        let error = |result: Result<Pattern, PatternError>| result.unwrap_err().to_string();
+        assert_eq!(error(Pattern::from_life106("#Life 1.06\n1 2 3\n")), "line 2: invalid cell '1 2 3'");
+        assert_eq!(error(Pattern::from_life106("#Life 1.06\n1\n")), "line 2: invalid cell '1'");
+        assert_eq!(error(Pattern::from_life106("x y\n")), "line 1: invalid cell 'x y'");
+        assert_eq!(error(Pattern::from_life105("#P 1\n*\n")), "line 1: invalid block position '1'");
+        assert_eq!(error(Pattern::from_life105("#P 0 0\n*o\n")), "line 2: unexpected character 'o'");
+        assert!(error(Pattern::from_life105("#R 23/39\n")).starts_with("line 1: invalid rule"));
+    }
+
+    #[test]
+    fn rejects_cells_too_far_apart() {
+        let result = Pattern::from_life106("#Life 1.06\n-2000000000 0\n2000000000 0\n");
+        assert!(matches!(result, Err(PatternError::TooLarge { width: 4000000001, height: 1 })));
+        let result = Pattern::from_coordinates(&[(i64::MIN, i64::MIN), (i64::MAX, i64::MAX)]);
+        assert!(matches!(result, Err(PatternError::TooLarge { .. })));
+        let result = Pattern::from_life105("#P 0 0\n*\n#P 0 100000\n*\n#P 10000 0\n*\n");
+        assert!(matches!(result, Err(PatternError::TooLarge { width: 10001, height: 100001 })));
+    }
+}
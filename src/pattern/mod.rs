@@ -0,0 +1,244 @@
+//! Patterns and the RLE, plaintext and Life 1.05/1.06 file formats.
+
+mod life;
+mod plaintext;
+mod rle;
+
+use std::fmt;
+use std::path::Path;
+use crate::grid::{Cell, Grid};
+use crate::rule::Rule;
+
+/// A two-state pattern independent of any file format. Cells are stored row by row
+/// with `true` for live cells.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pattern {
+    width: u32,
+    height: u32,
+    cells: Vec<bool>,
+    rule: Option<Rule>,
+    name: Option<String>,
+    comments: Vec<String>,
+}
+
+/// The most cells a parsed pattern may declare, so a malformed file cannot ask for an
+/// allocation of many gigabytes
+pub const MAX_CELLS: u64 = 1 << 28;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// The pattern file formats that can be read and written
+pub enum PatternFormat {
+    /// Run Length Encoded, `.rle`
+    Rle,
+    /// Rows of `.` and `O`, `.cells`
+    Plaintext,
+    /// Blocks of `.` and `*` rows, `.lif`
+    Life105,
+    /// One line per live cell, `.lif` or `.life`
+    Life106,
+}
+
+impl PatternFormat {
+    /// Guesses the format from the file extension, falling back to the header line
+    /// since both Life 1.05 and 1.06 files use `.lif`
+    pub fn detect(path: &Path, text: &str) -> PatternFormat {
+        let header = text.lines().next().unwrap_or("").trim();
+        if header.starts_with("#Life 1.05") {
+            return PatternFormat::Life105;
+        }
+        if header.starts_with("#Life 1.06") {
+            return PatternFormat::Life106;
+        }
+
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("cells") => PatternFormat::Plaintext,
+            Some("lif" | "life") => PatternFormat::Life106,
+            _ => PatternFormat::Rle,
+        }
+    }
+}
+
+#[derive(Debug)]
+/// Why a pattern could not be read or written
+pub enum PatternError {
+    /// The file could not be read or written
+    Io(std::io::Error),
+    /// The text is not a valid pattern
+    Parse {
+        /// Line of the text, starting at one
+        line: usize,
+        /// What is wrong with the line
+        message: String,
+    },
+    /// The pattern would hold more than `MAX_CELLS` cells
+    TooLarge {
+        /// Width the pattern would have
+        width: u64,
+        /// Height the pattern would have
+        height: u64,
+    },
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatternError::Io(err) => write!(f, "{}", err),
+            PatternError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            PatternError::TooLarge { width, height } => {
+                write!(f, "a {}x{} pattern is too large, the limit is {} cells", width, height, MAX_CELLS)
+            },
+        }
+    }
+}
+
+impl std::error::Error for PatternError {}
+
+impl From<std::io::Error> for PatternError {
+    fn from(err: std::io::Error) -> Self {
+        PatternError::Io(err)
+    }
+}
+
+impl Pattern {
+    /// A pattern of dead cells without a rule, name or comments
+    pub fn new(width: u32, height: u32) -> Pattern {
+        Pattern {
+            width,
+            height,
+            cells: vec![false; width as usize * height as usize],
+            rule: None,
+            name: None,
+            comments: Vec::new(),
+        }
+    }
+
+    // An empty pattern of a size read from a file, checked against `MAX_CELLS`
+    pub(crate) fn with_size(width: u64, height: u64) -> Result<Pattern, PatternError> {
+        match width.checked_mul(height) {
+            Some(cells) if cells <= MAX_CELLS => Ok(Pattern::new(width as u32, height as u32)),
+            _ => Err(PatternError::TooLarge { width, height }),
+        }
+    }
+
+    /// Builds a pattern from live cell coordinates, which may be negative, cropped to
+    /// their bounding box. Fails when the cells are too far apart, see `MAX_CELLS`.
+    pub fn from_coordinates(coordinates: &[(i64, i64)]) -> Result<Pattern, PatternError> {
+        let Some(&(first_x, first_y)) = coordinates.first() else {
+            return Ok(Pattern::new(0, 0));
+        };
+
+        let (mut min_x, mut min_y, mut max_x, mut max_y) = (first_x, first_y, first_x, first_y);
+        for &(x, y) in coordinates {
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x);
+            max_y = max_y.max(y);
+        }
+
+        // The spans saturate, which is still far beyond the limit
+        let width = max_x.abs_diff(min_x).saturating_add(1);
+        let height = max_y.abs_diff(min_y).saturating_add(1);
+        let mut pattern = Pattern::with_size(width, height)?;
+        for &(x, y) in coordinates {
+            pattern.set(x.abs_diff(min_x) as u32, y.abs_diff(min_y) as u32, true);
+        }
+        Ok(pattern)
+    }
+
+    /// Copies a rectangle of the grid, wrapping around its edges like the torus
+    pub fn from_grid(grid: &Grid, (x, y): (u32, u32), (width, height): (u32, u32)) -> Pattern {
+        let mut pattern = Pattern::new(width, height);
+        for py in 0..height {
+            for px in 0..width {
+                let alive = grid.get((x + px) % grid.width(), (y + py) % grid.height()).is_alive();
+                pattern.set(px, py, alive);
+            }
+        }
+        pattern
+    }
+
+    /// Parses a pattern in the given format
+    pub fn parse(text: &str, format: PatternFormat) -> Result<Pattern, PatternError> {
+        match format {
+            PatternFormat::Rle => Pattern::from_rle(text),
+            PatternFormat::Plaintext => Pattern::from_plaintext(text),
+            PatternFormat::Life105 => Pattern::from_life105(text),
+            PatternFormat::Life106 => Pattern::from_life106(text),
+        }
+    }
+
+    /// Writes the pattern in the given format
+    pub fn to_text(&self, format: PatternFormat) -> String {
+        match format {
+            PatternFormat::Rle => self.to_rle(),
+            PatternFormat::Plaintext => self.to_plaintext(),
+            PatternFormat::Life105 => self.to_life105(),
+            PatternFormat::Life106 => self.to_life106(),
+        }
+    }
+
+    /// Reads a pattern file, see `PatternFormat::detect`
+    pub fn load(path: impl AsRef<Path>) -> Result<Pattern, PatternError> {
+        let text = std::fs::read_to_string(path.as_ref())?;
+        Pattern::parse(&text, PatternFormat::detect(path.as_ref(), &text))
+    }
+
+    /// Writes the pattern in the format matching the file extension
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), PatternError> {
+        let format = PatternFormat::detect(path.as_ref(), "");
+        std::fs::write(path, self.to_text(format))?;
+        Ok(())
+    }
+
+    /// Width in cells
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Height in cells
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The rule given by the file, if any
+    pub fn rule(&self) -> Option<Rule> {
+        self.rule
+    }
+
+    /// Changes the rule written with the pattern
+    pub fn set_rule(&mut self, rule: Option<Rule>) {
+        self.rule = rule;
+    }
+
+    /// The name given by the file, if any
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Live cell coordinates relative to the top left corner
+    pub fn coordinates(&self) -> Vec<(i64, i64)> {
+        (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| self.get(x, y))
+            .map(|(x, y)| (x as i64, y as i64))
+            .collect()
+    }
+
+    /// Whether the cell at column `x` of row `y` is alive
+    pub fn get(&self, x: u32, y: u32) -> bool {
+        self.cells[(y * self.width + x) as usize]
+    }
+
+    /// Sets whether the cell at column `x` of row `y` is alive
+    pub fn set(&mut self, x: u32, y: u32, alive: bool) {
+        self.cells[(y * self.width + x) as usize] = alive;
+    }
+
+    /// The cells as they are uploaded to the simulation textures
+    pub fn to_grid(&self) -> Grid {
+        let cells = self.cells.iter()
+            .map(|&alive| if alive { Cell::ALIVE } else { Cell::DEAD })
+            .collect();
+        Grid::from_cells(self.width, self.height, cells)
+    }
+}
@@ -0,0 +1,98 @@
+use crate::pattern::{Pattern, PatternError};
+
+impl Pattern {
+    /// Parses a plaintext (.cells) pattern: `!` comment lines followed by rows of `.`
+    /// for dead cells and `O` for live ones. Rows may omit trailing dead cells.
+    pub fn from_plaintext(text: &str) -> Result<Pattern, PatternError> {
+        let mut name = None;
+        let mut comments = Vec::new();
+        let mut rows: Vec<Vec<bool>> = Vec::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim_end();
+            if let Some(comment) = line.strip_prefix('!') {
+                match comment.strip_prefix("Name:") {
+                    Some(value) => name = Some(value.trim().to_string()),
+                    None => comments.push(comment.trim().to_string()),
+                }
+                continue;
+            }
+
+            let row = line.chars()
+                .map(|c| match c {
+                    '.' => Ok(false),
+                    'O' | '*' => Ok(true),
+                    _ => Err(PatternError::Parse { line: index + 1, message: format!("unexpected character '{}'", c) }),
+                })
+                .collect::<Result<_, _>>()?;
+            rows.push(row);
+        }
+
+        // Blank lines at the end of the file are not rows of the pattern
+        while rows.last().is_some_and(|row| row.is_empty()) {
+            rows.pop();
+        }
+
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let mut pattern = Pattern::with_size(width as u64, rows.len() as u64)?;
+        for (y, row) in rows.iter().enumerate() {
+            for (x, &alive) in row.iter().enumerate() {
+                pattern.set(x as u32, y as u32, alive);
+            }
+        }
+        pattern.name = name;
+        pattern.comments = comments;
+        Ok(pattern)
+    }
+
+    /// Writes the pattern as a plaintext (.cells) pattern
+    pub fn to_plaintext(&self) -> String {
+        let mut text = String::new();
+        if let Some(name) = &self.name {
+            text += &format!("!Name: {}\n", name);
+        }
+        for comment in &self.comments {
+            text += &format!("!{}\n", comment);
+        }
+
+        // Rows are written in full so the width survives a round trip
+        for y in 0..self.height {
+            text.extend((0..self.width).map(|x| if self.get(x, y) { 'O' } else { '.' }));
+            text.push('\n');
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pattern::{Pattern, PatternError};
+
+    #[test]
+    fn round_trips() {
+        let text = "!Name: Blinker\n!A comment\n.....\n.OOO.\n.....\n";
+        let pattern = Pattern::from_plaintext(text).unwrap();
+        assert_eq!((pattern.width(), pattern.height()), (5, 3));
+        assert_eq!(pattern.coordinates(), vec![(1, 1), (2, 1), (3, 1)]);
+        assert_eq!(pattern.name(), Some("Blinker"));
+        assert_eq!(pattern.to_plaintext(), text);
+        assert_eq!(Pattern::from_plaintext(&pattern.to_plaintext()).unwrap(), pattern);
+    }
+
+    #[test]
+    fn pads_short_rows_and_drops_trailing_blank_lines() {
+        let pattern = Pattern::from_plaintext("*\n\n..O\n\n\n").unwrap();
+        assert_eq!((pattern.width(), pattern.height()), (3, 3));
+        assert_eq!(pattern.coordinates(), vec![(0, 0), (2, 2)]);
+        assert_eq!(Pattern::from_plaintext("!Only a comment\n").unwrap().width(), 0);
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        let error = Pattern::from_plaintext("!Name: Bad\n.O.\n.X.\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 3: unexpected character 'X'");
+
+        let text = format!("{}\n{}", "O".repeat(100000), ".\n".repeat(10000));
+        assert!(matches!(Pattern::from_plaintext(&text), Err(PatternError::TooLarge { width: 100000, height: 10001 })));
+    }
+}
@@ -0,0 +1,225 @@
+use crate::pattern::{Pattern, PatternError};
+
+// Longest line written to an RLE file, as recommended by the format
+const MAX_LINE_LENGTH: usize = 70;
+
+impl Pattern {
+    /// Parses a Run Length Encoded pattern, including its `x = m, y = n, rule = ...`
+    /// header and any `#` comment lines before it.
+    pub fn from_rle(text: &str) -> Result<Pattern, PatternError> {
+        let mut name = None;
+        let mut comments = Vec::new();
+        let mut pattern: Option<Pattern> = None;
+        let (mut x, mut y) = (0u32, 0u32);
+        let mut run: Option<u32> = None;
+
+        'lines: for (index, line) in text.lines().enumerate() {
+            let error = |message: String| PatternError::Parse { line: index + 1, message };
+            let line = line.trim();
+
+            let pattern = match &mut pattern {
+                Some(pattern) => pattern,
+                None => {
+                    if let Some(comment) = line.strip_prefix("#N") {
+                        name = Some(comment.trim().to_string());
+                    } else if let Some(comment) = line.strip_prefix('#') {
+                        // Keep #C comments and other lines such as #O (author) without the tag
+                        comments.push(comment.get(1..).unwrap_or("").trim().to_string());
+                    } else if !line.is_empty() {
+                        let mut header = parse_header(line).map_err(error)?;
+                        header.name = name.take();
+                        header.comments = std::mem::take(&mut comments);
+                        pattern = Some(header);
+                    }
+                    continue;
+                },
+            };
+
+            for c in line.chars() {
+                let count = match c {
+                    '0'..='9' => {
+                        let digit = c.to_digit(10).unwrap();
+                        let count = run.unwrap_or(0).checked_mul(10).and_then(|count| count.checked_add(digit));
+                        run = Some(count.ok_or_else(|| error("run count is too large".to_string()))?);
+                        continue;
+                    },
+                    _ if c.is_whitespace() => continue,
+                    _ => run.take().unwrap_or(1),
+                };
+
+                // Positions saturate, since any cell placed past the declared size is an error
+                match c {
+                    'b' | '.' => x = x.saturating_add(count),
+                    'o' | 'A'..='X' => {
+                        let end = x as u64 + count as u64;
+                        if count > 0 && (end > pattern.width as u64 || y >= pattern.height) {
+                            return Err(error(format!("cells at ({}, {}) lie outside the declared size", end - 1, y)));
+                        }
+                        for i in 0..count {
+                            pattern.set(x + i, y, true);
+                        }
+                        x += count;
+                    },
+                    '$' => {
+                        x = 0;
+                        y = y.saturating_add(count);
+                    },
+                    '!' => break 'lines,
+                    _ => return Err(error(format!("unexpected character '{}'", c))),
+                }
+            }
+        }
+
+        // Anything after the '!' terminator is ignored, and a missing terminator is tolerated
+        pattern.ok_or(PatternError::Parse { line: text.lines().count(), message: "missing header line".to_string() })
+    }
+
+    /// Writes the pattern as RLE, with its name, comments and rule
+    pub fn to_rle(&self) -> String {
+        let mut text = String::new();
+        if let Some(name) = &self.name {
+            text += &format!("#N {}\n", name);
+        }
+        for comment in &self.comments {
+            text += &format!("#C {}\n", comment);
+        }
+
+        text += &format!("x = {}, y = {}", self.width, self.height);
+        if let Some(rule) = self.rule {
+            text += &format!(", rule = {}", rule);
+        }
+        text.push('\n');
+
+        // Collect the runs, dropping dead cells at the end of rows and merging blank rows
+        let mut tokens = Vec::new();
+        let mut line_ends = 0;
+        for y in 0..self.height {
+            let row: Vec<bool> = (0..self.width).map(|x| self.get(x, y)).collect();
+            let Some(last_alive) = row.iter().rposition(|&alive| alive) else {
+                line_ends += 1;
+                continue;
+            };
+
+            if line_ends > 0 {
+                tokens.push(run_token(line_ends, '$'));
+            }
+            line_ends = 1;
+
+            let mut x = 0;
+            while x <= last_alive {
+                let length = row[x..=last_alive].iter().take_while(|&&alive| alive == row[x]).count();
+                tokens.push(run_token(length as u32, if row[x] { 'o' } else { 'b' }));
+                x += length;
+            }
+        }
+        tokens.push("!".to_string());
+
+        // Wrap the body at the maximum line length without splitting runs
+        let mut line = String::new();
+        for token in tokens {
+            if line.len() + token.len() > MAX_LINE_LENGTH {
+                text += &line;
+                text.push('\n');
+                line.clear();
+            }
+            line += &token;
+        }
+        text += &line;
+        text.push('\n');
+        text
+    }
+}
+
+fn run_token(count: u32, tag: char) -> String {
+    match count {
+        1 => tag.to_string(),
+        _ => format!("{}{}", count, tag),
+    }
+}
+
+fn parse_header(line: &str) -> Result<Pattern, String> {
+    let (mut width, mut height, mut rule) = (None, None, None);
+    for field in line.split(',') {
+        let (key, value) = field.split_once('=')
+            .ok_or_else(|| format!("expected 'key = value' in header, found '{}'", field.trim()))?;
+        let value = value.trim();
+        match key.trim() {
+            "x" => width = Some(value.parse::<u32>().map_err(|err| format!("invalid width: {}", err))?),
+            "y" => height = Some(value.parse::<u32>().map_err(|err| format!("invalid height: {}", err))?),
+            "rule" => rule = Some(value.parse().map_err(|err| format!("invalid rule: {}", err))?),
+            _ => {},
+        }
+    }
+
+    let width = width.ok_or("header is missing the width (x)")?;
+    let height = height.ok_or("header is missing the height (y)")?;
+    let mut pattern = Pattern::with_size(width as u64, height as u64).map_err(|err| err.to_string())?;
+    pattern.set_rule(rule);
+    Ok(pattern)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pattern::{Pattern, PatternError};
+    use crate::rule::Rule;
+
+    const GLIDER: &str = "#N Glider\n#C A comment\nx = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n";
+
+    fn parse_error(text: &str) -> String {
+        match Pattern::from_rle(text) {
+            Err(PatternError::Parse { message, .. }) => message,
+            result => panic!("expected a parse error, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        let pattern = Pattern::from_rle(GLIDER).unwrap();
+        assert_eq!(pattern.coordinates(), vec![(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+        assert_eq!(pattern.name(), Some("Glider"));
+        assert_eq!(pattern.rule(), Some(Rule::CONWAY));
+        assert_eq!(pattern.to_rle(), GLIDER);
+        assert_eq!(Pattern::from_rle(&pattern.to_rle()).unwrap(), pattern);
+
+        // Long runs are wrapped without being split, and blank rows are merged
+        let mut wide = Pattern::new(200, 5);
+        for x in (0..200).step_by(3) {
+            wide.set(x, 4, true);
+        }
+        let text = wide.to_rle();
+        assert!(text.lines().all(|line| line.len() <= 70));
+        assert!(text.contains("4$"));
+        assert_eq!(Pattern::from_rle(&text).unwrap(), wide);
+    }
+
+    #[test]
+    fn reads_multistate_cells_and_ignores_trailing_text() {
+        let pattern = Pattern::from_rle("x = 4, y = 2\n2A\n$b2B! trailing $$ text").unwrap();
+        assert_eq!(pattern.coordinates(), vec![(0, 0), (1, 0), (1, 1), (2, 1)]);
+        assert_eq!(pattern.rule(), None);
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        assert_eq!(parse_error("#C only a comment\n"), "missing header line");
+        assert!(parse_error("x = 3\nbo!").contains("height"));
+        assert!(parse_error("x = 3, y = -1\nbo!").contains("invalid height"));
+        assert!(parse_error("x = 3, y = 3, rule = B9/S23\nbo!").contains("invalid rule"));
+        assert_eq!(parse_error("x = 3, y = 3\nbqo!"), "unexpected character 'q'");
+    }
+
+    #[test]
+    fn rejects_cells_outside_the_declared_size() {
+        assert!(parse_error("x = 3, y = 3\n4o!").contains("(3, 0)"));
+        assert!(parse_error("x = 3, y = 3\n3$o!").contains("(0, 3)"));
+        assert!(Pattern::from_rle("x = 3, y = 3\n3o$0o10b!").is_ok());
+    }
+
+    #[test]
+    fn rejects_overflowing_runs() {
+        assert_eq!(parse_error("x = 3, y = 3\n99999999999o!"), "run count is too large");
+        assert!(parse_error("x = 3, y = 3\n4294967295b4294967295bo!").contains("outside the declared size"));
+        assert!(parse_error("x = 3, y = 3\n4294967295$4294967295$o!").contains("outside the declared size"));
+        assert!(parse_error("x = 4000000000, y = 4000000000\no!").contains("too large"));
+    }
+}
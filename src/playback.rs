@@ -0,0 +1,137 @@
+use std::fmt;
+
+// Fastest rate reachable with `faster`
+const MAX_GENERATIONS_PER_FRAME: u32 = 1024;
+// Slowest rate reachable with `slower`
+const MAX_FRAMES_PER_GENERATION: u32 = 64;
+
+// How quickly the simulation advances relative to the display refresh
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StepRate {
+    GenerationsPerFrame(u32),
+    FramesPerGeneration(u32),
+}
+
+impl StepRate {
+    pub fn faster(self) -> StepRate {
+        match self {
+            StepRate::FramesPerGeneration(frames) if frames > 2 => StepRate::FramesPerGeneration(frames / 2),
+            StepRate::FramesPerGeneration(_) => StepRate::GenerationsPerFrame(1),
+            StepRate::GenerationsPerFrame(generations) => {
+                StepRate::GenerationsPerFrame((generations * 2).min(MAX_GENERATIONS_PER_FRAME))
+            },
+        }
+    }
+
+    pub fn slower(self) -> StepRate {
+        match self {
+            StepRate::GenerationsPerFrame(generations) if generations > 1 => StepRate::GenerationsPerFrame(generations / 2),
+            StepRate::GenerationsPerFrame(_) => StepRate::FramesPerGeneration(2),
+            StepRate::FramesPerGeneration(frames) => {
+                StepRate::FramesPerGeneration((frames * 2).min(MAX_FRAMES_PER_GENERATION))
+            },
+        }
+    }
+}
+
+impl fmt::Display for StepRate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StepRate::GenerationsPerFrame(generations) => write!(f, "{} generations per frame", generations),
+            StepRate::FramesPerGeneration(frames) => write!(f, "one generation every {} frames", frames),
+        }
+    }
+}
+
+// Decides how many generations to run for each displayed frame
+pub struct Playback {
+    pub paused: bool,
+    pub rate: StepRate,
+    frame: u32,
+    pending_steps: u32,
+}
+
+impl Default for Playback {
+    fn default() -> Self {
+        Playback {
+            paused: false,
+            rate: StepRate::GenerationsPerFrame(1),
+            frame: 0,
+            pending_steps: 0,
+        }
+    }
+}
+
+impl Playback {
+    // Pauses and queues a single generation for the next frame
+    pub fn single_step(&mut self) {
+        self.paused = true;
+        self.pending_steps += 1;
+    }
+
+    pub fn next_frame(&mut self) -> u32 {
+        let steps = std::mem::take(&mut self.pending_steps);
+        if self.paused {
+            return steps;
+        }
+
+        steps + match self.rate {
+            StepRate::GenerationsPerFrame(generations) => generations,
+            StepRate::FramesPerGeneration(frames) => {
+                self.frame = (self.frame + 1) % frames;
+                (self.frame == 0) as u32
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rates_double_and_halve() {
+        let slower: Vec<_> = std::iter::successors(Some(StepRate::GenerationsPerFrame(1)), |rate| Some(rate.slower())).take(4).collect();
+        assert_eq!(slower[1..], [StepRate::FramesPerGeneration(2), StepRate::FramesPerGeneration(4), StepRate::FramesPerGeneration(8)]);
+        let faster: Vec<_> = std::iter::successors(Some(slower[3]), |rate| Some(rate.faster())).skip(1).take(5).collect();
+        assert_eq!(faster, [
+            StepRate::FramesPerGeneration(4),
+            StepRate::FramesPerGeneration(2),
+            StepRate::GenerationsPerFrame(1),
+            StepRate::GenerationsPerFrame(2),
+            StepRate::GenerationsPerFrame(4),
+        ]);
+
+        assert_eq!(StepRate::GenerationsPerFrame(MAX_GENERATIONS_PER_FRAME).faster(), StepRate::GenerationsPerFrame(MAX_GENERATIONS_PER_FRAME));
+        assert_eq!(StepRate::FramesPerGeneration(MAX_FRAMES_PER_GENERATION).slower(), StepRate::FramesPerGeneration(MAX_FRAMES_PER_GENERATION));
+    }
+
+    #[test]
+    fn frames_run_the_rate() {
+        let mut playback = Playback { rate: StepRate::GenerationsPerFrame(8), ..Playback::default() };
+        assert_eq!(playback.next_frame(), 8);
+        assert_eq!(playback.next_frame(), 8);
+
+        // One generation on every third frame
+        playback.rate = StepRate::FramesPerGeneration(3);
+        let steps: Vec<_> = (0..9).map(|_| playback.next_frame()).collect();
+        assert_eq!(steps.iter().sum::<u32>(), 3);
+        assert_eq!(steps.chunks(3).map(|frames| frames.iter().sum::<u32>()).collect::<Vec<_>>(), [1, 1, 1]);
+    }
+
+    #[test]
+    fn single_steps_pause() {
+        let mut playback = Playback { rate: StepRate::GenerationsPerFrame(4), ..Playback::default() };
+        playback.single_step();
+        playback.single_step();
+        assert!(playback.paused);
+        assert_eq!(playback.next_frame(), 2);
+        assert_eq!(playback.next_frame(), 0);
+
+        // A step queued before resuming runs along with the rate
+        playback.single_step();
+        playback.paused = false;
+        assert_eq!(playback.next_frame(), 5);
+        assert_eq!(playback.next_frame(), 4);
+    }
+}
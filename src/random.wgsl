@@ -0,0 +1,39 @@
+struct Soup {
+    seed_low: u32,
+    seed_high: u32,
+    threshold: u32,
+    origin: vec2<u32>,     // position of texel (0, 0) in the soup
+    world_size: vec2<u32>, // size of the soup, which positions wrap around
+};
+
+@group(1) @binding(0) var<uniform> soup: Soup;
+
+// lowbias32, see `mix` in random.rs
+fn mix(value: u32) -> u32 {
+    var x = value;
+    x ^= x >> 16u;
+    x *= 0x7feb352du;
+    x ^= x >> 15u;
+    x *= 0x846ca68bu;
+    x ^= x >> 16u;
+    return x;
+}
+
+@compute
+@workgroup_size(16, 16)
+fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let dimensions = textureDimensions(output_texture);
+    if global_id.x >= dimensions.x || global_id.y >= dimensions.y {
+        return;
+    }
+
+    // Each cell only depends on the seed and its coordinates
+    let key = mix(soup.seed_low ^ mix(soup.seed_high));
+    let position = (global_id.xy + soup.origin) % soup.world_size;
+    let hash = mix(mix(key + position.x) + position.y);
+    if hash >> 8u < soup.threshold {
+        store_cell(global_id.xy, vec4<f32>(1.0, 1.0, 0.0, 0.0));
+    } else {
+        store_cell(global_id.xy, vec4<f32>(-1.0, 0.0, 0.0, 0.0));
+    }
+}
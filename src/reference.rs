@@ -0,0 +1,177 @@
+//! A CPU implementation of the simulation, for checking the GPU results.
+
+use crate::boundary::Boundary;
+use crate::grid::{Cell, Grid};
+use crate::rule::Rule;
+
+// Decay applied to the trail every generation, as in transition.wgsl
+const TRAIL_DECAY: f32 = 0.99;
+
+/// Advances the grid by one generation on the CPU with the same semantics as the
+/// transition shader: the neighborhood joined at the edges by `boundary`, `state` set to
+/// +1/-1 by the rule (a surviving cell keeps its value) and `trail = max(state, trail * 0.99)`.
+pub fn step(grid: &Grid, rule: Rule, boundary: Boundary) -> Grid {
+    let (width, height) = (grid.width(), grid.height());
+    let mut next = Grid::new(width, height);
+
+    for y in 0..height {
+        for x in 0..width {
+            let mut cell = grid.get(x, y);
+
+            // Sum neighbors, finding the ones beyond the edges with the boundary
+            let mut count = 0;
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    if dx == 0 && dy == 0 {
+                        continue;
+                    }
+                    let neighbor = boundary.locate((x as i64 + dx, y as i64 + dy), (width, height));
+                    count += neighbor.map_or(Cell::DEAD, |(x, y)| grid.get(x, y)).is_alive() as u32;
+                }
+            }
+
+            // Apply the birth and survival rules
+            if cell.is_alive() {
+                if !rule.survives(count) {
+                    cell.state = -1.0;
+                }
+            } else if rule.is_born(count) {
+                cell.state = 1.0;
+            } else {
+                cell.state = -1.0;
+            }
+
+            cell.trail = cell.state.max(cell.trail * TRAIL_DECAY);
+            next.set(x, y, cell);
+        }
+    }
+
+    next
+}
+
+/// Advances the grid by the given number of generations, see `step`
+pub fn step_n(grid: &Grid, rule: Rule, boundary: Boundary, generations: u32) -> Grid {
+    let mut grid = grid.clone();
+    for _ in 0..generations {
+        grid = step(&grid, rule, boundary);
+    }
+    grid
+}
+
+/// Returns the coordinates of the first cell whose state differs between the grids
+pub fn first_difference(a: &Grid, b: &Grid) -> Option<(u32, u32)> {
+    assert_eq!((a.width(), a.height()), (b.width(), b.height()), "grid sizes differ");
+    (0..a.height())
+        .flat_map(|y| (0..a.width()).map(move |x| (x, y)))
+        .find(|&(x, y)| a.get(x, y).is_alive() != b.get(x, y).is_alive())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::CellFormat;
+    use crate::simulation::SimulationOptions;
+    use crate::testing::{alive_cells, gpu_simulation_if_supported, grid_with, GLIDER, R_PENTOMINO};
+
+    const BLINKER: &[(u32, u32)] = &[(1, 0), (1, 1), (1, 2)];
+
+    fn sorted(mut cells: Vec<(u32, u32)>) -> Vec<(u32, u32)> {
+        cells.sort_by_key(|&(x, y)| (y, x));
+        cells
+    }
+
+    // Runs the same grid on the GPU in `format`, comparing trails within its precision.
+    // Truncating a 16 bit trail loses at most 1/65535 per generation, while r8uint keeps
+    // too few bits to compare them. Returns early when the adapter cannot store `format`.
+    fn assert_matches_gpu(grid: &Grid, rule: Rule, generations: u32, format: CellFormat) {
+        let expected = step_n(grid, rule, Boundary::Torus, generations);
+        let Some(mut simulation) = gpu_simulation_if_supported(SimulationOptions {
+            size: (grid.width(), grid.height()),
+            rule,
+            format,
+            ..SimulationOptions::default()
+        }) else {
+            return;
+        };
+        simulation.set_grid(grid);
+        simulation.step(generations);
+        let actual = simulation.read_cells_blocking().unwrap();
+        assert_eq!(first_difference(&expected, &actual), None, "GPU and CPU disagree in {}", format);
+        let tolerance = match format {
+            CellFormat::Float => 1e-6,
+            CellFormat::Uint32 => 0.01,
+            CellFormat::Uint8 => return,
+        };
+        for (e, a) in expected.cells().iter().zip(actual.cells()) {
+            assert!((e.trail - a.trail).abs() < tolerance, "trail {} != {}", e.trail, a.trail);
+        }
+    }
+
+    #[test]
+    fn blinker_oscillates() {
+        let grid = grid_with(8, 8, BLINKER, (3, 2));
+        let horizontal = step(&grid, Rule::CONWAY, Boundary::Torus);
+        assert_eq!(sorted(alive_cells(&horizontal)), vec![(3, 3), (4, 3), (5, 3)]);
+        assert_eq!(alive_cells(&step(&horizontal, Rule::CONWAY, Boundary::Torus)), alive_cells(&grid));
+    }
+
+    #[test]
+    fn glider_translates_and_wraps() {
+        let grid = grid_with(10, 7, GLIDER, (7, 4));
+        let moved = step_n(&grid, Rule::CONWAY, Boundary::Torus, 4);
+        assert_eq!(sorted(alive_cells(&moved)), sorted(alive_cells(&grid_with(10, 7, GLIDER, (8, 5)))));
+    }
+
+    #[test]
+    fn dead_cells_decay() {
+        let grid = grid_with(6, 6, &[(2, 2)], (0, 0));
+        let next = step(&grid, Rule::CONWAY, Boundary::Torus);
+        assert_eq!(next.get(2, 2).state, -1.0);
+        assert_eq!(next.get(2, 2).trail, 0.99);
+        assert_eq!(next.get(0, 0), Cell::DEAD);
+    }
+
+    #[test]
+    fn rules_change_births() {
+        // A lone domino dies in Life but grows in Seeds (B2/S)
+        let grid = grid_with(8, 8, &[(3, 3), (4, 3)], (0, 0));
+        assert_eq!(step(&grid, Rule::CONWAY, Boundary::Torus).population(), 0);
+        assert_eq!(step(&grid, "B2/S".parse().unwrap(), Boundary::Torus).population(), 4);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter, run with --ignored"]
+    fn gpu_matches_blinker() {
+        assert_matches_gpu(&grid_with(16, 16, BLINKER, (0, 7)), Rule::CONWAY, 5, CellFormat::Uint32);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter, run with --ignored"]
+    fn gpu_matches_glider_across_tiles() {
+        // The glider crosses both wrapping edges of a grid whose sides are not powers of two.
+        // 28x42 is a multiple of the 14x14 tile stride, so no overhanging tile recomputes the
+        // cells next to the edge, while 30x20 is not.
+        assert_matches_gpu(&grid_with(28, 42, GLIDER, (20, 30)), Rule::CONWAY, 60, CellFormat::Uint32);
+        assert_matches_gpu(&grid_with(30, 20, GLIDER, (20, 10)), Rule::CONWAY, 60, CellFormat::Uint32);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter, run with --ignored"]
+    fn gpu_matches_r_pentomino() {
+        assert_matches_gpu(&grid_with(64, 64, R_PENTOMINO, (30, 30)), Rule::CONWAY, 200, CellFormat::Uint32);
+        assert_matches_gpu(&grid_with(50, 45, R_PENTOMINO, (20, 20)), "B36/S23".parse().unwrap(), 100, CellFormat::Uint32);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter storing rg32float cells, run with --ignored"]
+    fn gpu_matches_float_trails() {
+        assert_matches_gpu(&grid_with(30, 20, GLIDER, (20, 10)), Rule::CONWAY, 60, CellFormat::Float);
+        assert_matches_gpu(&grid_with(50, 45, R_PENTOMINO, (20, 20)), "B36/S23".parse().unwrap(), 100, CellFormat::Float);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter storing r8uint cells, run with --ignored"]
+    fn gpu_matches_in_r8uint() {
+        assert_matches_gpu(&grid_with(50, 45, R_PENTOMINO, (20, 20)), Rule::CONWAY, 150, CellFormat::Uint8);
+    }
+}
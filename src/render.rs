@@ -1,37 +1,9 @@
+use std::fmt;
 use std::sync::Arc;
-use wgpu::CommandBuffer;
-use winit::{dpi::PhysicalSize, window::Window};
-use rand;
-
-const SIMULATION_WIDTH: u32 = 1024;
-const SIMULATION_HEIGHT: u32 = 1024;
-
-struct TextureResource {
-    texture: wgpu::Texture,
-    view: wgpu::TextureView,
-    sampler: wgpu::Sampler,
-    transition_bind_group: Option<wgpu::BindGroup>,
-    display_bind_group: Option<wgpu::BindGroup>,
-}
-
-struct TextureSwapper {
-    index: usize,
-    texture_resources: [TextureResource; 2],
-}
-
-impl TextureSwapper {
-    fn get_read_resource(&self) -> &TextureResource {
-        &self.texture_resources[self.index]
-    }
-
-    fn get_write_resource(&self) -> &TextureResource {
-        &self.texture_resources[(self.index + 1) % 2]
-    }
-
-    fn swap(&mut self) {
-        self.index = (self.index + 1) % 2;
-    }
-}
+use winit::{dpi::{PhysicalPosition, PhysicalSize}, window::Window};
+use wgpu_automata::{Anchor, Boundary, Cell, Generator, Pattern, Rule, SimulationError, SimulationOptions};
+use crate::camera::{Camera, CameraUniform};
+use crate::space::Space;
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
@@ -55,132 +27,208 @@ impl Vertex {
     }
 }
 
+// Where a chunk is drawn, see `Chunk` in display.wgsl
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct ChunkUniform {
+    origin: [i32; 2],
+    size: [u32; 2],
+    world_size: [u32; 2],
+    halo: u32,
+    _padding: u32,
+}
+
 const QUAD_VERTICES: &[Vertex] = &[
     Vertex { position: [0.0, 0.0, 0.0] },
     Vertex { position: [1.0, 0.0, 0.0] },
     Vertex { position: [0.0, 1.0, 0.0] },
     Vertex { position: [1.0, 1.0, 0.0] }
 ];
-pub struct RenderState<'a> {
-    _instance: wgpu::Instance,
-    surface: wgpu::Surface<'a>,
-    device: wgpu::Device,
-    queue: wgpu::Queue,
-    surface_config: wgpu::SurfaceConfiguration,
-    texture_size: (u32, u32),
-
-    texture_swapper: Option<TextureSwapper>,
-    transition_pipeline: Option<wgpu::ComputePipeline>,
-    display_pipeline: Option<wgpu::RenderPipeline>,
-    vertex_buffer: Option<wgpu::Buffer>,
-    surface_size_buffer: Option<wgpu::Buffer>,
-    surface_size_bind_group: Option<wgpu::BindGroup>,
+
+// Why a `RenderState` could not be created
+#[derive(Debug)]
+pub enum RenderError {
+    CreateSurface(wgpu::CreateSurfaceError),
+    Simulation(SimulationError),
+    // The adapter cannot present to the surface of the window
+    IncompatibleSurface,
+    // The surface does not support the present mode
+    PresentMode(wgpu::PresentMode),
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::CreateSurface(err) => write!(f, "failed to create the surface: {}", err),
+            RenderError::Simulation(err) => write!(f, "{}", err),
+            RenderError::IncompatibleSurface => write!(f, "the adapter cannot present to the window"),
+            RenderError::PresentMode(mode) => write!(f, "the window does not support the {:?} present mode", mode),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RenderError::CreateSurface(err) => Some(err),
+            RenderError::Simulation(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<SimulationError> for RenderError {
+    fn from(err: SimulationError) -> Self {
+        RenderError::Simulation(err)
+    }
+}
+
+// Why a frame could not be drawn, see `RenderState::draw`
+#[derive(Debug)]
+pub enum DrawError {
+    // No frame could be acquired, the generations were still run
+    Surface(wgpu::SurfaceError),
+    // The universe could not grow, see `Universe::step`
+    Simulation(SimulationError),
+}
+
+impl fmt::Display for DrawError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DrawError::Surface(err) => write!(f, "failed to acquire a frame: {}", err),
+            DrawError::Simulation(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for DrawError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DrawError::Surface(err) => Some(err),
+            DrawError::Simulation(err) => Some(err),
+        }
+    }
+}
+
+impl From<SimulationError> for DrawError {
+    fn from(err: SimulationError) -> Self {
+        DrawError::Simulation(err)
+    }
+}
+
+// Collects the settings of a `RenderState`, see `RenderState::builder`
+pub struct RenderStateBuilder {
+    window: Arc<Window>,
+    options: SimulationOptions,
+    chunk_size: Option<u32>,
+    unbounded: bool,
+    present_mode: wgpu::PresentMode,
 }
 
-impl<'a> RenderState<'a> {
-    pub async fn new(window: Arc<Window>) -> RenderState<'a> {
+impl RenderStateBuilder {
+    // The grid size, rule, storage format and kernel settings of the simulation
+    pub fn simulation(mut self, options: SimulationOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    // Caps the sides of the chunks the world is split into, see `World::with_instance`,
+    // or sets the side of the chunks of an unbounded universe
+    pub fn chunk_size(mut self, chunk_size: Option<u32>) -> Self {
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    // Simulates an unbounded universe instead of a torus, see `Universe`
+    pub fn unbounded(mut self, unbounded: bool) -> Self {
+        self.unbounded = unbounded;
+        self
+    }
+
+    pub fn present_mode(mut self, present_mode: wgpu::PresentMode) -> Self {
+        self.present_mode = present_mode;
+        self
+    }
+
+    // Creates the surface, the space on an adapter that can present to it and the
+    // display pipeline. The simulation options are checked against the adapter limits
+    // and the present mode against the surface.
+    pub async fn build<'a>(self) -> Result<RenderState<'a>, RenderError> {
         // Create the instance
         let instance = wgpu::Instance::default();
-        
-        // Create the surface
-        let surface = instance.create_surface(Arc::clone(&window)).unwrap();
 
-        // Create the adapter
-        let adapter = instance.request_adapter(&wgpu::RequestAdapterOptions {
-            power_preference: wgpu::PowerPreference::default(),
-            compatible_surface: Some(&surface),
-            force_fallback_adapter: false,
-        })
-        .await.unwrap();
-
-        // Pick a device
-        let (device, queue) = pollster::block_on(adapter.request_device(
-            &wgpu::DeviceDescriptor {
-                label: None,
-                required_features: wgpu::Features::empty(),
-                required_limits: wgpu::Limits::default(),
-            },
-            None, // Trace path
-        ))
-        .unwrap();
-        println!("Using device {}", adapter.get_info().name);
+        // Create the surface
+        let surface = instance.create_surface(Arc::clone(&self.window)).map_err(RenderError::CreateSurface)?;
+
+        // Create the space on an adapter that can present to the surface
+        let space = Space::with_instance(
+            &instance,
+            Some(&surface),
+            &self.options,
+            self.chunk_size,
+            self.unbounded,
+        ).await?;
+        println!("Using device {}", space.adapter().get_info().name);
+
+        // The automatic modes fall back to a supported mode
+        let automatic = matches!(self.present_mode, wgpu::PresentMode::AutoVsync | wgpu::PresentMode::AutoNoVsync);
+        let capabilities = surface.get_capabilities(space.adapter());
+        if !automatic && !capabilities.present_modes.contains(&self.present_mode) {
+            return Err(RenderError::PresentMode(self.present_mode));
+        }
 
         // Configure the surface
-        let window_size = window.inner_size();
-        let surface_config = surface.get_default_config(
-            &adapter, 
-            window_size.width, 
-            window_size.height).unwrap();
-        surface.configure(&device, &surface_config);
-
-        let texture_size = (SIMULATION_WIDTH, SIMULATION_HEIGHT);   
+        let window_size = self.window.inner_size();
+        let surface_config = wgpu::SurfaceConfiguration {
+            present_mode: self.present_mode,
+            ..surface.get_default_config(
+                space.adapter(),
+                window_size.width,
+                window_size.height,
+            ).ok_or(RenderError::IncompatibleSurface)?
+        };
+        surface.configure(space.device(), &surface_config);
+
+        let camera = Camera::default();
+        let display = Display::new(
+            &space,
+            surface_config.format,
+            camera.uniform((surface_config.width, surface_config.height), space.torus_size()),
+        );
 
-        Self {
-            _instance: instance,
+        Ok(RenderState {
+            instance,
             surface,
-            device,
-            queue,
+            space,
             surface_config,
-            texture_size,
-            texture_swapper: None,
-            transition_pipeline: None,
-            display_pipeline: None,
-            vertex_buffer: None,
-            surface_size_buffer: None,
-            surface_size_bind_group: None,
-        }
+            camera,
+            display,
+        })
     }
+}
 
-    pub fn create_pipelines(&mut self) {
-        // Create bind group layout for transition
-        let transition_bind_group_layout =
-            self.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                entries: &[
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 0,
-                        visibility: wgpu::ShaderStages::COMPUTE,
-                        ty: wgpu::BindingType::Texture {
-                            multisampled: false,
-                            view_dimension: wgpu::TextureViewDimension::D2,
-                            sample_type: wgpu::TextureSampleType::Float { filterable: false },
-                        },
-                        count: None,
-                    },
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 1,
-                        visibility: wgpu::ShaderStages::COMPUTE,
-                        ty: wgpu::BindingType::StorageTexture { 
-                            access: wgpu::StorageTextureAccess::WriteOnly, 
-                            format: wgpu::TextureFormat::Rg32Float, 
-                            view_dimension: wgpu::TextureViewDimension::D2,
-                        },
-                        count: None,
-                    },
-                ],
-                label: Some("transition_bind_group_layout"),
-            });
-
-        // Create pipeline layout for transition
-        let transition_pipeline_layout =
-            self.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                label: Some("transition_pipeline_layout"),
-                bind_group_layouts: &[&transition_bind_group_layout],
-                push_constant_ranges: &[],
-            });
+// The pipeline and bindings drawing the current generation, tied to the device of the
+// space they were created for
+struct Display {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    // Bind group i of a chunk binds its texture i and where it is drawn
+    bind_groups: Vec<[wgpu::BindGroup; 2]>,
+    // The revision of the space the chunks were bound for
+    revision: u64,
+    vertex_buffer: wgpu::Buffer,
+    camera_buffer: wgpu::Buffer,
+    camera_bind_group: wgpu::BindGroup,
+}
 
-        // Create the transition pipeline
-        let transition_shader_module = self.device.create_shader_module(wgpu::include_wgsl!("transition.wgsl"));
-        self.transition_pipeline = Some(self.device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("compute_pipeline"),
-            layout: Some(&transition_pipeline_layout),
-            module: &transition_shader_module,
-            entry_point: "main",
-            compilation_options: Default::default(),
-        }));
+impl Display {
+    fn new(space: &Space, surface_format: wgpu::TextureFormat, camera: CameraUniform) -> Display {
+        let device = space.device();
+        let queue = space.queue();
 
         // Create bind group layout for display
         let display_bind_group_layout =
-            self.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 entries: &[
                     wgpu::BindGroupLayoutEntry {
                         binding: 0,
@@ -188,37 +236,37 @@ impl<'a> RenderState<'a> {
                         ty: wgpu::BindingType::Texture {
                             multisampled: false,
                             view_dimension: wgpu::TextureViewDimension::D2,
-                            sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                            sample_type: space.format().sample_type(),
                         },
                         count: None,
                     },
                     wgpu::BindGroupLayoutEntry {
                         binding: 1,
-                        visibility: wgpu::ShaderStages::FRAGMENT,
-                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::NonFiltering),
+                        visibility: wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::VERTEX,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
                         count: None,
                     },
                 ],
                 label: Some("display_bind_group_layout"),
             });
 
-        // Create surface size uniform
-        self.surface_size_buffer = Some(self.device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("surface_size_buffer"),
-            size: (std::mem::size_of::<u32>() * 2) as u64,
+        // Create camera uniform
+        let camera_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("camera_buffer"),
+            size: std::mem::size_of::<CameraUniform>() as u64,
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
-        }));
+        });
 
-        // Upload the surface size
-        self.queue.write_buffer(
-            &self.surface_size_buffer.as_ref().unwrap(), 
-            0, 
-            bytemuck::cast_slice(&[self.surface_config.width, self.surface_config.height])
-        );
+        // Upload the camera
+        queue.write_buffer(&camera_buffer, 0, bytemuck::bytes_of(&camera));
 
-        // Create surface size bind group layout
-        let surface_size_bind_group_layout = self.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        // Create camera bind group layout
+        let camera_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             entries: &[
                 wgpu::BindGroupLayoutEntry {
                     binding: 0,
@@ -231,36 +279,39 @@ impl<'a> RenderState<'a> {
                     count: None,
                 }
             ],
-            label: Some("surface_size_uniform_layout"),
+            label: Some("camera_uniform_layout"),
         });
 
-        // Create surface size bind group
-        self.surface_size_bind_group = Some(self.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &surface_size_bind_group_layout,
+        // Create camera bind group
+        let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &camera_bind_group_layout,
             entries: &[
                 wgpu::BindGroupEntry {
                     binding: 0,
                     resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
-                        buffer: self.surface_size_buffer.as_ref().unwrap(),
+                        buffer: &camera_buffer,
                         offset: 0,
                         size: None,
                     }),
                 }
             ],
             label: Some("camera_bind_group"),
-        }));
+        });
 
         // Create pipeline layout for display
         let display_pipeline_layout =
-            self.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
                 label: Some("display_pipeline_layout"),
-                bind_group_layouts: &[&display_bind_group_layout, &surface_size_bind_group_layout],
+                bind_group_layouts: &[&display_bind_group_layout, &camera_bind_group_layout],
                 push_constant_ranges: &[],
             });
         
         // Create the display pipeline
-        let display_shader_module = self.device.create_shader_module(wgpu::include_wgsl!("display.wgsl"));
-        self.display_pipeline = Some(self.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        let display_shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("display.wgsl"),
+            source: wgpu::ShaderSource::Wgsl(space.format().shader_source(include_str!("display.wgsl")).into()),
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("display_pipeline"),
             layout: Some(&display_pipeline_layout),
             vertex: wgpu::VertexState {
@@ -275,7 +326,7 @@ impl<'a> RenderState<'a> {
                 module: &display_shader_module,
                 entry_point: "fs_main",
                 targets: &[Some(wgpu::ColorTargetState {
-                    format: self.surface_config.format,
+                    format: surface_format,
                     blend: Some(wgpu::BlendState::REPLACE),
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
@@ -297,174 +348,129 @@ impl<'a> RenderState<'a> {
                 alpha_to_coverage_enabled: false,
             },
             multiview: None,
-        }));
+        });
 
         // Create vertex buffer
-        self.vertex_buffer = Some(self.device.create_buffer(&wgpu::BufferDescriptor {
+        let vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("quad_vertex_buffer"),
-            size: (std::mem::size_of::<Vertex>() * QUAD_VERTICES.len()) as u64,
+            size: std::mem::size_of_val(QUAD_VERTICES) as u64,
             usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
-        }));
-
-        // Upload the vertices
-        self.queue.write_buffer(&self.vertex_buffer.as_ref().unwrap(), 0, bytemuck::cast_slice(&QUAD_VERTICES));
-
-        // Create the texture resources
-        let mut tex_a = self.create_texture_resource();
-        let mut tex_b = self.create_texture_resource();
-
-        // Create bindings for reading and writing to textures
-        self.create_transition_bind_group(&mut tex_a, &mut tex_b, &transition_bind_group_layout);
-        self.create_transition_bind_group(&mut tex_b, &mut tex_a, &transition_bind_group_layout);
-
-        // Create bindings for displaying textures
-        self.create_display_bind_group(&mut tex_a, &display_bind_group_layout);
-        self.create_display_bind_group(&mut tex_b, &display_bind_group_layout);
-
-        // Create the texture swapper
-        self.texture_swapper = Some(TextureSwapper {
-            index: 0,
-            texture_resources: [
-                tex_a,
-                tex_b,
-            ],
-        });
-    }
-
-    fn create_texture_resource(&self) -> TextureResource {
-        let texture = self.device.create_texture(
-            &wgpu::TextureDescriptor{
-                label: Some("texture"),
-                size: wgpu::Extent3d {
-                    width: self.texture_size.0,
-                    height: self.texture_size.1,
-                    depth_or_array_layers: 1,
-                },
-                mip_level_count: 1,
-                sample_count: 1,
-                dimension: wgpu::TextureDimension::D2,
-                format: wgpu::TextureFormat::Rg32Float,
-                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::COPY_DST,
-                view_formats: &[],
-            }
-        );
-
-        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-
-        let sampler = self.device.create_sampler(&wgpu::SamplerDescriptor {
-            address_mode_u: wgpu::AddressMode::Repeat,
-            address_mode_v: wgpu::AddressMode::Repeat,
-            address_mode_w: wgpu::AddressMode::ClampToEdge,
-            ..Default::default()
         });
 
-        TextureResource {
-            texture,
-            view,
-            sampler,
-            transition_bind_group: None,
-            display_bind_group: None,
-        }
+        // Upload the vertices
+        queue.write_buffer(&vertex_buffer, 0, bytemuck::cast_slice(QUAD_VERTICES));
+
+        let mut display = Display {
+            pipeline,
+            bind_group_layout: display_bind_group_layout,
+            bind_groups: Vec::new(),
+            revision: space.revision(),
+            vertex_buffer,
+            camera_buffer,
+            camera_bind_group,
+        };
+        display.bind_chunks(space);
+        display
     }
 
-    fn create_transition_bind_group(
-        &self, 
-        read: &mut TextureResource, 
-        write: &mut TextureResource, 
-        layout: &wgpu::BindGroupLayout
-    ) {
-        read.transition_bind_group = Some(self.device.create_bind_group(
-            &wgpu::BindGroupDescriptor {
-                layout: layout,
-                entries: &[
-                    wgpu::BindGroupEntry {
-                        binding: 0,
-                        resource: wgpu::BindingResource::TextureView(&read.view),
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 1,
-                        resource: wgpu::BindingResource::TextureView(&write.view),
-                    },
-                ],
-                label: Some("transition_bind_group"),
-            }
-        ));
+    // Creates the bindings for displaying the textures of each chunk of the space
+    fn bind_chunks(&mut self, space: &Space) {
+        let device = space.device();
+        let (world_width, world_height) = space.torus_size().unwrap_or((0, 0));
+        self.bind_groups = space.chunks().into_iter().map(|(origin, size, chunk)| {
+            let chunk_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("chunk_buffer"),
+                size: std::mem::size_of::<ChunkUniform>() as u64,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            space.queue().write_buffer(&chunk_buffer, 0, bytemuck::bytes_of(&ChunkUniform {
+                origin: [origin.0 as i32, origin.1 as i32],
+                size: [size.0, size.1],
+                world_size: [world_width, world_height],
+                halo: space.halo(),
+                _padding: 0,
+            }));
+            [0, 1].map(|index| Display::create_bind_group(device, chunk.texture_view(index), &chunk_buffer, &self.bind_group_layout))
+        }).collect();
+        self.revision = space.revision();
     }
 
-    fn create_display_bind_group(
-        &self, 
-        resource: &mut TextureResource, 
+    fn create_bind_group(
+        device: &wgpu::Device,
+        texture_view: &wgpu::TextureView,
+        chunk_buffer: &wgpu::Buffer,
         layout: &wgpu::BindGroupLayout
-    ) {
-        resource.display_bind_group = Some(self.device.create_bind_group(
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(
             &wgpu::BindGroupDescriptor {
-                layout: layout,
+                layout,
                 entries: &[
                     wgpu::BindGroupEntry {
                         binding: 0,
-                        resource: wgpu::BindingResource::TextureView(&resource.view),
+                        resource: wgpu::BindingResource::TextureView(texture_view),
                     },
                     wgpu::BindGroupEntry {
                         binding: 1,
-                        resource: wgpu::BindingResource::Sampler(&resource.sampler),
+                        resource: chunk_buffer.as_entire_binding(),
                     },
                 ],
                 label: Some("display_bind_group"),
             }
-        ));
+        )
     }
+}
 
-    fn compute_work_group_count(
-        (width, height): (u32, u32),
-        (workgroup_width, workgroup_height): (u32, u32),
-    ) -> (u32, u32) {
-        let x = (width + workgroup_width - 1) / workgroup_width;
-        let y = (height + workgroup_height - 1) / workgroup_height;
-    
-        (x, y)
-    }
-
-    pub fn transition(&mut self) -> CommandBuffer {
-        let texture_resource = self.texture_swapper.as_ref().unwrap().get_read_resource();
-        let texture_size = texture_resource.texture.size();
-
-        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("transition_encoder"),
-        });
-
-        {
-            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor{
-                label: Some("transition_compute_pass"),
-                timestamp_writes: None,
-            });
-
-            let (dispatch_with, dispatch_height) = RenderState::compute_work_group_count(
-                (texture_size.width, texture_size.height), 
-                (14, 14)
-            );
-            // The work groups are actually 16x16 but we use 14x14 because the threads around the edges
-            // are only used for populating the shared memory.
+pub struct RenderState<'a> {
+    instance: wgpu::Instance,
+    surface: wgpu::Surface<'a>,
+    space: Space,
+    surface_config: wgpu::SurfaceConfiguration,
+    camera: Camera,
+    display: Display,
+}
 
-            compute_pass.set_pipeline(self.transition_pipeline.as_ref().unwrap());
-            compute_pass.set_bind_group(0, texture_resource.transition_bind_group.as_ref().unwrap(), &[]);
-            compute_pass.dispatch_workgroups(dispatch_with, dispatch_height, 1);
+impl RenderState<'_> {
+    // Starts building a renderer for the window with the default simulation options and
+    // vsync
+    pub fn builder(window: Arc<Window>) -> RenderStateBuilder {
+        RenderStateBuilder {
+            window,
+            options: SimulationOptions::default(),
+            chunk_size: None,
+            unbounded: false,
+            present_mode: wgpu::PresentMode::Fifo,
         }
-
-        self.texture_swapper.as_mut().unwrap().swap();
-        encoder.finish()
     }
 
-    pub fn draw(&mut self) {
-        let output = self.surface.get_current_texture().unwrap();
+    // Advances the space by the given number of generations and displays the result.
+    // The transitions of a torus are batched into the same command buffer as the display
+    // pass, a universe steps first to allocate its chunks. When no frame can be acquired
+    // the generations are still run and the error is returned, see `reconfigure` for the
+    // errors that need the surface to be configured again.
+    pub fn draw(&mut self, generations: u32) -> Result<(), DrawError> {
+        let output = match self.surface.get_current_texture() {
+            Ok(output) => output,
+            Err(err) => {
+                self.space.step(generations)?;
+                return Err(DrawError::Surface(err));
+            },
+        };
         let output_view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
-        let input_bind_group = &self.texture_swapper.as_ref().unwrap().get_read_resource().display_bind_group;
-        let surface_size_bind_group = self.surface_size_bind_group.as_ref().unwrap();
 
-        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        let mut encoder = self.space.device().create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("display_encoder"),
         });
 
+        match &mut self.space {
+            Space::World(world) => world.transition(&mut encoder, generations),
+            Space::Universe(universe) => universe.step(generations)?,
+        }
+        if self.display.revision != self.space.revision() {
+            self.display.bind_chunks(&self.space);
+        }
+
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("display_render_pass"),
@@ -486,94 +492,143 @@ impl<'a> RenderState<'a> {
                 occlusion_query_set: None,
             });
 
-            render_pass.set_pipeline(&self.display_pipeline.as_ref().unwrap());
-            render_pass.set_bind_group(0, input_bind_group.as_ref().unwrap(), &[]);
-            render_pass.set_bind_group(1, surface_size_bind_group, &[]);
-            render_pass.set_vertex_buffer(0, self.vertex_buffer.as_ref().unwrap().slice(..));
-            render_pass.draw(0..4, 0..1);
-        }
+            render_pass.set_pipeline(&self.display.pipeline);
+            render_pass.set_bind_group(1, &self.display.camera_bind_group, &[]);
+            render_pass.set_vertex_buffer(0, self.display.vertex_buffer.slice(..));
 
-        let transition_command_buffer = self.transition();
+            // Each chunk draws its interior once per copy of the torus on the surface
+            let surface_size = (self.surface_config.width, self.surface_config.height);
+            let (_, (columns, rows)) = self.camera.visible_copies(surface_size, self.space.torus_size());
+            for ((_, _, chunk), bind_groups) in self.space.chunks().into_iter().zip(&self.display.bind_groups) {
+                render_pass.set_bind_group(0, &bind_groups[chunk.read_index()], &[]);
+                render_pass.draw(0..4, 0..columns * rows);
+            }
+        }
 
-        self.queue.submit([transition_command_buffer, encoder.finish()].into_iter());
+        self.space.queue().submit([encoder.finish()]);
         output.present();
+        Ok(())
+    }
+
+    // Configures the surface again after it was lost or became outdated
+    pub fn reconfigure(&mut self) {
+        self.surface.configure(self.space.device(), &self.surface_config);
+    }
+
+    // Starts reading back the grid to restore it if the device is lost
+    pub fn take_snapshot(&mut self) {
+        self.space.take_snapshot();
+    }
+
+    // Keeps the snapshot once it was read back, without blocking the frame
+    pub fn poll_snapshot(&mut self) {
+        self.space.poll_snapshot();
+    }
+
+    // Recreates the space and the display on a new device, restoring the last
+    // snapshot, see `World::recover`
+    pub fn recover(&mut self) -> Result<(), SimulationError> {
+        pollster::block_on(self.space.recover(&self.instance, Some(&self.surface)))?;
+        self.reconfigure();
+        self.display = Display::new(&self.space, self.surface_config.format, self.camera_uniform());
+        Ok(())
     }
 
     pub fn resize(&mut self, new_size: PhysicalSize<u32>) {
         if new_size.width > 0 && new_size.height > 0 {
             self.surface_config.width = new_size.width;
             self.surface_config.height = new_size.height;
-            self.surface.configure(&self.device, &self.surface_config);
-
-            // Upload the surface size
-            if self.surface_size_buffer.is_some() {
-                self.queue.write_buffer(
-                    &self.surface_size_buffer.as_ref().unwrap(), 
-                    0, 
-                    bytemuck::cast_slice(&[self.surface_config.width, self.surface_config.height])
-                );
-            }
+            self.surface.configure(self.space.device(), &self.surface_config);
+
+            self.update_camera();
         }
     }
 
-    pub fn set_texture(&mut self, data: &[u8]) {
-        let texture = &self.texture_swapper.as_ref().unwrap().get_write_resource().texture;
+    fn camera_uniform(&self) -> CameraUniform {
+        self.camera.uniform((self.surface_config.width, self.surface_config.height), self.space.torus_size())
+    }
 
-        self.queue.write_texture(
-            wgpu::ImageCopyTexture {
-                texture: texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            data,
-            wgpu::ImageDataLayout {
-                offset: 0,
-                bytes_per_row: Some(self.texture_size.0 * 2 * std::mem::size_of::<f32>() as u32),
-                rows_per_image: Some(self.texture_size.1),
-            },
-            wgpu::Extent3d {
-                width: self.texture_size.0,
-                height: self.texture_size.1,
-                depth_or_array_layers: 1,
-            },
-        );
+    fn update_camera(&self) {
+        // Upload the camera
+        self.space.queue().write_buffer(&self.display.camera_buffer, 0, bytemuck::bytes_of(&self.camera_uniform()));
+    }
 
-        self.texture_swapper.as_mut().unwrap().swap();
+    // Moves the view by a distance in surface pixels
+    pub fn pan(&mut self, delta: (f64, f64)) {
+        self.camera.pan(delta, self.space.torus_size());
+        self.update_camera();
     }
 
-    pub fn randomize(&mut self) {
-        let width = self.texture_size.0 as usize;
-        let height = self.texture_size.1 as usize;
-        let capacity = width * height * 2;
-        let mut data = vec![0f32; capacity];
-        
-        for y in 0..(height / 2) {
-            for x in 0..(width / 2) {
-                let random_value: f32 = match rand::random::<bool>() {
-                    true => 1.0,
-                    _ => -1.0,
-                };
-
-                if x <= (width / 2 - y) {
-                    continue;
-                }
+    pub fn zoom_around(&mut self, position: PhysicalPosition<f64>, factor: f64) {
+        self.camera.zoom_around(position, factor, self.surface_config.height, self.space.torus_size());
+        self.update_camera();
+    }
 
-                if x >= (width / 4 + (height / 2 - y)) {
-                    continue;
-                }
+    pub fn reset_camera(&mut self) {
+        self.camera = Camera::default();
+        self.update_camera();
+    }
 
-                /*if (width / 2 - x) < (width/8) && (height / 2 - y) < (height/8) {
-                    continue;
-                }*/
+    // Maps a position on the surface to texel coordinates through the camera, the same
+    // way vs_main in display.wgsl does. The result is not wrapped.
+    pub fn surface_to_texel(&self, position: PhysicalPosition<f64>) -> (i64, i64) {
+        let (x, y) = self.camera.surface_to_texel(position, self.surface_config.height);
+        (x.floor() as i64, y.floor() as i64)
+    }
 
-                data[(y * width + x) * 2] = random_value;
-                data[(y * width + (width - x - 1)) * 2] = random_value;
-                data[((height - y - 1) * width + x) * 2] = random_value;
-                data[((height - y - 1) * width + (width - x - 1)) * 2] = random_value;
+    // Sets every cell on the line between two unwrapped texel positions, uploading
+    // only those cells
+    pub fn paint_line(&mut self, (x0, y0): (i64, i64), (x1, y1): (i64, i64), cell: Cell) -> Result<(), SimulationError> {
+        let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+        let (step_x, step_y) = ((x1 - x0).signum(), (y1 - y0).signum());
+        let (mut x, mut y, mut error) = (x0, y0, dx + dy);
+
+        // Bresenham's line algorithm
+        loop {
+            self.space.set_cell((x, y), cell)?;
+            if x == x1 && y == y1 {
+                return Ok(());
+            }
+            if 2 * error >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if 2 * error <= dx {
+                error += dx;
+                y += step_y;
             }
         }
+    }
 
-        self.set_texture(bytemuck::cast_slice(data.as_slice()));
+    pub fn space(&self) -> &Space {
+        &self.space
     }
-}
\ No newline at end of file
+
+    // Reads back the cells as a pattern, see `Space::export_pattern`
+    pub fn export_pattern(&mut self) -> Result<Pattern, wgpu::BufferAsyncError> {
+        self.space.export_pattern()
+    }
+
+    // Resizes the grid, see `World::resize`, and binds the new textures for display
+    pub fn resize_grid(&mut self, size: (u32, u32), anchor: Anchor) -> Result<(), SimulationError> {
+        self.space.resize(size, anchor)?;
+        self.display = Display::new(&self.space, self.surface_config.format, self.camera_uniform());
+        Ok(())
+    }
+
+    pub fn set_rule(&mut self, rule: Rule) -> Result<(), SimulationError> {
+        self.space.set_rule(rule)
+    }
+
+    pub fn set_boundary(&mut self, boundary: Boundary) -> Result<(), SimulationError> {
+        self.space.set_boundary(boundary)
+    }
+
+    pub fn randomize(&mut self, generator: Generator, seed: u64, density: f64) -> Result<(), SimulationError> {
+        self.space.randomize(generator, seed, density)
+    }
+
+    pub fn center_pattern(&mut self, pattern: &Pattern) -> Result<(), SimulationError> {
+        self.space.center_pattern(pattern)
+    }
+}
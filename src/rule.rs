@@ -0,0 +1,172 @@
+//! Life-like rules and their rulestrings.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// An outer-totalistic Life-like rule. Bit n of `birth` is set when a dead cell with n live
+/// neighbors becomes alive, and bit n of `survival` is set when a live cell with n live
+/// neighbors stays alive. The layout matches the `Rule` uniform in transition.wgsl.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Rule {
+    birth: u32,
+    survival: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// Why a rulestring could not be parsed
+pub enum ParseRuleError {
+    /// There are no birth (B) conditions
+    MissingBirth,
+    /// There are no survival (S) conditions
+    MissingSurvival,
+    /// The character is not a neighbor count from 0 to 8 or a B, S or / in place
+    UnexpectedCharacter(char),
+}
+
+impl fmt::Display for ParseRuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseRuleError::MissingBirth => write!(f, "rule is missing the birth (B) conditions"),
+            ParseRuleError::MissingSurvival => write!(f, "rule is missing the survival (S) conditions"),
+            ParseRuleError::UnexpectedCharacter(c) => write!(f, "unexpected character '{}' in rule", c),
+        }
+    }
+}
+
+impl std::error::Error for ParseRuleError {}
+
+impl Rule {
+    /// Conway's Game of Life, B3/S23
+    pub const CONWAY: Rule = Rule { birth: 1 << 3, survival: (1 << 2) | (1 << 3) };
+
+    /// Whether a dead cell with `count` live neighbors becomes alive
+    pub fn is_born(&self, count: u32) -> bool {
+        (self.birth >> count) & 1 != 0
+    }
+
+    /// Whether a live cell with `count` live neighbors stays alive
+    pub fn survives(&self, count: u32) -> bool {
+        (self.survival >> count) & 1 != 0
+    }
+
+    // Parses the digits of one half of a rulestring into a neighbor count mask
+    fn parse_counts(digits: &str) -> Result<u32, ParseRuleError> {
+        let mut mask = 0;
+        for c in digits.chars() {
+            match c.to_digit(10) {
+                Some(n) if n <= 8 => mask |= 1 << n,
+                _ => return Err(ParseRuleError::UnexpectedCharacter(c)),
+            }
+        }
+        Ok(mask)
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Rule::CONWAY
+    }
+}
+
+impl FromStr for Rule {
+    type Err = ParseRuleError;
+
+    // Accepts B/S notation ("B36/S23", "b3s23", "S23/B3") as well as the
+    // older survival/birth notation ("23/36").
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        if !s.contains(['B', 'b', 'S', 's']) {
+            let (survival, birth) = s.split_once('/').ok_or(ParseRuleError::MissingBirth)?;
+            return Ok(Rule {
+                birth: Rule::parse_counts(birth)?,
+                survival: Rule::parse_counts(survival)?,
+            });
+        }
+
+        let mut birth = None;
+        let mut survival = None;
+        for part in s.split('/').flat_map(|part| {
+            // Allow the slash to be omitted, as in "B3S23"
+            let split = part.char_indices()
+                .skip(1)
+                .find(|&(_, c)| matches!(c, 'B' | 'b' | 'S' | 's'))
+                .map_or(part.len(), |(i, _)| i);
+            [&part[..split], &part[split..]]
+        }) {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some('B' | 'b') if birth.is_none() => birth = Some(Rule::parse_counts(chars.as_str())?),
+                Some('S' | 's') if survival.is_none() => survival = Some(Rule::parse_counts(chars.as_str())?),
+                Some(c) => return Err(ParseRuleError::UnexpectedCharacter(c)),
+                None => {},
+            }
+        }
+
+        Ok(Rule {
+            birth: birth.ok_or(ParseRuleError::MissingBirth)?,
+            survival: survival.ok_or(ParseRuleError::MissingSurvival)?,
+        })
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "B")?;
+        for n in (0..=8).filter(|&n| self.is_born(n)) {
+            write!(f, "{}", n)?;
+        }
+        write!(f, "/S")?;
+        for n in (0..=8).filter(|&n| self.survives(n)) {
+            write!(f, "{}", n)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Result<Rule, ParseRuleError> {
+        s.parse()
+    }
+
+    #[test]
+    fn parses_notations() {
+        let highlife = Rule { birth: (1 << 3) | (1 << 6), survival: (1 << 2) | (1 << 3) };
+        for s in ["B36/S23", "b36/s23", "B36S23", "S23/B36", "s23b36", " B63/S32 ", "23/36"] {
+            assert_eq!(parse(s), Ok(highlife), "{}", s);
+        }
+        assert_eq!(parse("B3/S23"), Ok(Rule::CONWAY));
+        assert_eq!(parse("B/S"), Ok(Rule { birth: 0, survival: 0 }));
+        assert_eq!(parse("B012345678/S"), Ok(Rule { birth: 0x1ff, survival: 0 }));
+        assert_eq!(parse("/3"), Ok(Rule { birth: 1 << 3, survival: 0 }));
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        assert_eq!(parse("B3"), Err(ParseRuleError::MissingSurvival));
+        assert_eq!(parse("S23"), Err(ParseRuleError::MissingBirth));
+        assert_eq!(parse("23"), Err(ParseRuleError::MissingBirth));
+        assert_eq!(parse(""), Err(ParseRuleError::MissingBirth));
+        assert_eq!(parse("B9/S23"), Err(ParseRuleError::UnexpectedCharacter('9')));
+        assert_eq!(parse("B3/S2x"), Err(ParseRuleError::UnexpectedCharacter('x')));
+        assert_eq!(parse("23/3/4"), Err(ParseRuleError::UnexpectedCharacter('/')));
+        assert_eq!(parse("B3/B3/S23"), Err(ParseRuleError::UnexpectedCharacter('B')));
+        assert_eq!(parse("B3/X/S23"), Err(ParseRuleError::UnexpectedCharacter('X')));
+        assert_eq!(ParseRuleError::UnexpectedCharacter('9').to_string(), "unexpected character '9' in rule");
+    }
+
+    #[test]
+    fn displays_and_round_trips() {
+        assert_eq!(Rule::CONWAY.to_string(), "B3/S23");
+        assert_eq!(Rule::default(), Rule::CONWAY);
+        for s in ["B36/S23", "B/S", "B0/S8", "B1357/S1357", "B012345678/S012345678"] {
+            assert_eq!(parse(s).unwrap().to_string(), s);
+        }
+        assert_eq!(parse("S23/B63").unwrap().to_string(), "B36/S23");
+        assert_eq!(parse("23/36").unwrap().to_string(), "B36/S23");
+    }
+}
@@ -0,0 +1,1826 @@
+//! A grid simulated on the GPU.
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::task::{Context, Poll, Waker};
+use crate::activity::ActivityTracker;
+use crate::bits::BitKernel;
+use crate::bounds::{BoundsFinder, BoundsPipeline};
+use crate::boundary::{Boundary, BoundaryUniform};
+use crate::format::CellFormat;
+use crate::generator::Generator;
+use crate::grid::{Anchor, Cell, Grid};
+use crate::random::SoupUniform;
+use crate::pattern::Pattern;
+use crate::rule::Rule;
+
+/// Settings fixed when a `Simulation` is created
+#[derive(Clone)]
+pub struct SimulationOptions {
+    /// Width and height of the grid in cells
+    pub size: (u32, u32),
+    /// Initial rule, see `Simulation::set_rule`
+    pub rule: Rule,
+    /// Initial boundary, see `Simulation::set_boundary`
+    pub boundary: Boundary,
+    /// Texel format of the cell textures
+    pub format: CellFormat,
+    /// Compute pipeline used to advance the grid
+    pub kernel: Kernel,
+    /// Only transition the tiles near cells that changed, see `ActivityTracker`. Trails
+    /// stop fading once they are no longer visible. Only used by the texture kernel.
+    pub skip_stable_tiles: bool,
+    /// Generations the texture kernel advances in workgroup memory per dispatch, less than
+    /// half the workgroup size. Each extra generation widens the halo by one cell on each
+    /// side, so fewer cells are written per workgroup and more of them are recomputed.
+    /// Two or three trade global memory traffic for compute best.
+    pub block_generations: u32,
+    /// Edge of the square workgroups of the texture kernel. The cells around the edges
+    /// only load the halo, so each workgroup advances a tile two cells narrower.
+    pub workgroup_size: u32,
+    /// Requests a software adapter
+    pub force_fallback_adapter: bool,
+}
+
+/// The compute pipeline that advances the grid
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Kernel {
+    /// One invocation per cell reading the cell textures, see transition.wgsl
+    #[default]
+    Texture,
+    /// One invocation per 32 cells packed into the bits of a u32, see bits.wgsl. The
+    /// grid width must be a multiple of 32. Boundaries other than the torus use the
+    /// texture kernel.
+    BitPacked,
+}
+
+impl fmt::Display for Kernel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Kernel::Texture => write!(f, "texture"),
+            Kernel::BitPacked => write!(f, "bits"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+/// A kernel name that is not texture or bits
+pub struct ParseKernelError(String);
+
+impl fmt::Display for ParseKernelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown kernel '{}', expected texture or bits", self.0)
+    }
+}
+
+impl std::error::Error for ParseKernelError {}
+
+impl FromStr for Kernel {
+    type Err = ParseKernelError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "texture" => Ok(Kernel::Texture),
+            "bits" => Ok(Kernel::BitPacked),
+            _ => Err(ParseKernelError(s.to_string())),
+        }
+    }
+}
+
+/// Why a `Simulation` could not be created from its options, or a `Universe` could not
+/// grow
+#[derive(Debug)]
+pub enum SimulationError {
+    /// No adapter matched the instance, surface and `force_fallback_adapter`
+    NoAdapter,
+    /// The adapter cannot write cells of this format from shaders
+    UnsupportedFormat {
+        /// Name of the adapter
+        adapter: String,
+        /// The format asked for
+        format: CellFormat,
+    },
+    /// A side of the grid is zero or longer than the largest texture of the adapter
+    InvalidSize {
+        /// The size asked for
+        size: (u32, u32),
+        /// The largest side of a texture on the adapter
+        max: u32,
+    },
+    /// The bit-packed kernel was chosen for a grid width that is not a multiple of 32
+    BitKernelWidth(u32),
+    /// The adapter cannot run square workgroups with this edge
+    WorkgroupTooLarge {
+        /// Name of the adapter
+        adapter: String,
+        /// The workgroup edge asked for
+        workgroup_size: u32,
+    },
+    /// `block_generations` is zero or at least half the workgroup size
+    BlockGenerations {
+        /// The generations per dispatch asked for
+        block_generations: u32,
+        /// The workgroup edge asked for
+        workgroup_size: u32,
+    },
+    /// Several generations per dispatch were combined with the bit-packed kernel or with
+    /// skipping stable tiles
+    BlockedKernel,
+    /// A `World` split into several chunks was asked for another kernel or boundary than
+    /// the texture kernel on a torus, see `World`
+    ChunkedWorld,
+    /// A `Universe` was asked for another kernel than the texture kernel advancing one
+    /// generation per dispatch, or for a boundary, which it does not have
+    UnboundedUniverse,
+    /// The rule gives birth to dead cells without live neighbors, which would fill the
+    /// whole of a `Universe` at once
+    BirthWithoutNeighbors(Rule),
+    /// The adapter refused to create the device
+    RequestDevice(wgpu::RequestDeviceError),
+    /// The device ran out of memory for the textures of a new chunk
+    OutOfMemory(wgpu::Error),
+    /// The live cells could not be read back, usually because the device was lost
+    ReadBack(wgpu::BufferAsyncError),
+}
+
+impl fmt::Display for SimulationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimulationError::NoAdapter => write!(f, "no suitable adapter found"),
+            SimulationError::UnsupportedFormat { adapter, format } => {
+                write!(f, "{} cannot store {:?} cells", adapter, format.texture_format())
+            },
+            SimulationError::InvalidSize { size: (width, height), max } => {
+                write!(f, "cannot create a {}x{} grid, each side must be between 1 and {}", width, height, max)
+            },
+            SimulationError::BitKernelWidth(width) => {
+                write!(f, "the bit-packed kernel needs a grid width that is a multiple of 32, not {}", width)
+            },
+            SimulationError::WorkgroupTooLarge { adapter, workgroup_size } => {
+                write!(f, "{} cannot run {}x{} workgroups", adapter, workgroup_size, workgroup_size)
+            },
+            SimulationError::BlockGenerations { block_generations, workgroup_size } => write!(
+                f,
+                "cannot advance {} generations per dispatch in {}x{} workgroups",
+                block_generations,
+                workgroup_size,
+                workgroup_size,
+            ),
+            SimulationError::BlockedKernel => {
+                write!(f, "advancing several generations per dispatch needs the texture kernel without skipping stable tiles")
+            },
+            SimulationError::ChunkedWorld => write!(
+                f,
+                "a world of several chunks needs the texture kernel on a torus, advancing one generation per dispatch without skipping stable tiles",
+            ),
+            SimulationError::UnboundedUniverse => write!(
+                f,
+                "an unbounded universe has no boundary and needs the texture kernel, advancing one generation per dispatch without skipping stable tiles",
+            ),
+            SimulationError::BirthWithoutNeighbors(rule) => {
+                write!(f, "rule {} gives birth to cells without live neighbors, which would fill an unbounded universe", rule)
+            },
+            SimulationError::RequestDevice(err) => write!(f, "failed to create the device: {}", err),
+            SimulationError::OutOfMemory(err) => write!(f, "failed to allocate a chunk: {}", err),
+            SimulationError::ReadBack(err) => write!(f, "failed to read back the live cells: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for SimulationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SimulationError::RequestDevice(err) => Some(err),
+            SimulationError::OutOfMemory(err) => Some(err),
+            SimulationError::ReadBack(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl Default for SimulationOptions {
+    fn default() -> Self {
+        SimulationOptions {
+            size: (1024, 1024),
+            rule: Rule::default(),
+            boundary: Boundary::default(),
+            format: CellFormat::default(),
+            kernel: Kernel::default(),
+            skip_stable_tiles: false,
+            block_generations: 1,
+            workgroup_size: 16,
+            force_fallback_adapter: false,
+        }
+    }
+}
+
+struct TextureResource {
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    transition_bind_group: wgpu::BindGroup,
+}
+
+struct TextureSwapper {
+    index: usize,
+    texture_resources: [TextureResource; 2],
+}
+
+impl TextureSwapper {
+    fn get_read_resource(&self) -> &TextureResource {
+        &self.texture_resources[self.index]
+    }
+
+    fn get_write_resource(&self) -> &TextureResource {
+        &self.texture_resources[(self.index + 1) % 2]
+    }
+
+    fn swap(&mut self) {
+        self.index = (self.index + 1) % 2;
+    }
+}
+
+// The adapter and device of a simulation, shared by the chunks of a `World`
+#[derive(Clone)]
+pub(crate) struct Gpu {
+    pub adapter: Arc<wgpu::Adapter>,
+    pub device: Arc<wgpu::Device>,
+    pub queue: Arc<wgpu::Queue>,
+    // Set by the device lost callback
+    pub device_lost: Arc<AtomicBool>,
+    // The first error raised outside of an error scope, see `Simulation::take_error`
+    pub error: Arc<Mutex<Option<wgpu::Error>>>,
+}
+
+impl Gpu {
+    // Creates a device able to run the transition workgroups and store cells in the
+    // format of `options`, with textures up to the largest size of the adapter
+    pub async fn request(
+        instance: &wgpu::Instance,
+        compatible_surface: Option<&wgpu::Surface<'_>>,
+        options: &SimulationOptions,
+    ) -> Result<Gpu, SimulationError> {
+        // Create the adapter
+        let adapter = instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::default(),
+            compatible_surface,
+            force_fallback_adapter: options.force_fallback_adapter,
+        })
+        .await.ok_or(SimulationError::NoAdapter)?;
+
+        // Writing to R8Uint textures is not part of WebGPU and needs an adapter specific feature
+        let format_features = adapter.get_texture_format_features(options.format.texture_format());
+        if !format_features.allowed_usages.contains(wgpu::TextureUsages::STORAGE_BINDING) {
+            return Err(SimulationError::UnsupportedFormat {
+                adapter: adapter.get_info().name,
+                format: options.format,
+            });
+        }
+        let required_features = match options.format {
+            CellFormat::Uint8 => wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES,
+            _ => wgpu::Features::empty(),
+        };
+
+        // Check the workgroups fit the adapter, raising the default limits for larger
+        // textures and workgroups larger than 16x16
+        let limits = adapter.limits();
+        let too_large = || SimulationError::WorkgroupTooLarge {
+            adapter: adapter.get_info().name,
+            workgroup_size: options.workgroup_size,
+        };
+        let invocations = options.workgroup_size.checked_mul(options.workgroup_size).ok_or_else(too_large)?;
+        let workgroup_storage = invocations.checked_mul(16).ok_or_else(too_large)?; // one vec4<f32> per cell
+        if options.workgroup_size > limits.max_compute_workgroup_size_x.min(limits.max_compute_workgroup_size_y)
+            || invocations > limits.max_compute_invocations_per_workgroup
+            || workgroup_storage > limits.max_compute_workgroup_storage_size
+        {
+            return Err(too_large());
+        }
+        let default_limits = wgpu::Limits::default();
+        let required_limits = wgpu::Limits {
+            // Limits can't be raised once the device exists, so take the adapter's full
+            // texture size: `resize`, `recover` and the chunks of a `World` sized to this
+            // limit would otherwise need a new device and every texture copied over
+            max_texture_dimension_2d: limits.max_texture_dimension_2d,
+            max_compute_invocations_per_workgroup: invocations.max(default_limits.max_compute_invocations_per_workgroup),
+            max_compute_workgroup_size_x: options.workgroup_size.max(default_limits.max_compute_workgroup_size_x),
+            max_compute_workgroup_size_y: options.workgroup_size.max(default_limits.max_compute_workgroup_size_y),
+            max_compute_workgroup_storage_size: workgroup_storage.max(default_limits.max_compute_workgroup_storage_size),
+            ..default_limits
+        };
+
+        // Pick a device
+        let (device, queue) = adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                label: None,
+                required_features,
+                required_limits,
+            },
+            None, // Trace path
+        )
+        .await.map_err(SimulationError::RequestDevice)?;
+        log::info!("Using device {}", adapter.get_info().name);
+
+        // Once the device is lost every call fails until the simulation is recovered, so
+        // errors are only kept while the device is alive
+        let device_lost = Arc::new(AtomicBool::new(false));
+        device.set_device_lost_callback({
+            let device_lost = Arc::clone(&device_lost);
+            move |reason, message| {
+                log::warn!("Device lost ({:?}) {}", reason, message);
+                device_lost.store(true, Ordering::Release);
+            }
+        });
+        let error = Arc::new(Mutex::new(None));
+        device.on_uncaptured_error(Box::new({
+            let device_lost = Arc::clone(&device_lost);
+            let error = Arc::clone(&error);
+            move |new_error| {
+                if !device_lost.load(Ordering::Acquire) {
+                    log::error!("wgpu error: {}", new_error);
+                    error.lock().unwrap().get_or_insert(new_error);
+                }
+            }
+        }));
+
+        Ok(Gpu {
+            adapter: Arc::new(adapter),
+            device: Arc::new(device),
+            queue: Arc::new(queue),
+            device_lost,
+            error,
+        })
+    }
+}
+
+// The layouts, shader modules and pipelines of a simulation. They only depend on the
+// format, the workgroups and the block generations, so the chunks of a `World` or a
+// `Universe` share them and only create their own textures and bind groups.
+pub(crate) struct Pipelines {
+    transition_bind_group_layout: wgpu::BindGroupLayout,
+    // Binds the rule and boundary uniforms
+    rule_bind_group_layout: wgpu::BindGroupLayout,
+    // Binds the soup uniform
+    uniform_bind_group_layout: wgpu::BindGroupLayout,
+    // Kept to create the activity trackers, see `create_grid_resources`
+    transition_shader_module: wgpu::ShaderModule,
+    transition_pipeline: wgpu::ComputePipeline,
+    // Advances `block_generations` generations per dispatch, see main_blocked
+    blocked_pipeline: Option<wgpu::ComputePipeline>,
+    soup_pipeline: wgpu::ComputePipeline,
+    // Created on first use by `encode_live_bounds`
+    bounds_pipeline: OnceLock<BoundsPipeline>,
+}
+
+impl Pipelines {
+    pub fn new(device: &wgpu::Device, options: &SimulationOptions) -> Pipelines {
+        // Create bind group layout for transition
+        let transition_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            sample_type: options.format.sample_type(),
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::StorageTexture {
+                            access: wgpu::StorageTextureAccess::WriteOnly,
+                            format: options.format.texture_format(),
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                        },
+                        count: None,
+                    },
+                ],
+                label: Some("transition_bind_group_layout"),
+            });
+
+        let uniform_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+
+        // Create the layout of the soup uniform
+        let uniform_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[uniform_entry(0)],
+            label: Some("uniform_bind_group_layout"),
+        });
+
+        // Create the layout of the rule and boundary uniforms
+        let rule_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[uniform_entry(0), uniform_entry(1)],
+            label: Some("rule_bind_group_layout"),
+        });
+
+        // Create pipeline layout for transition
+        let transition_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("transition_pipeline_layout"),
+                bind_group_layouts: &[&transition_bind_group_layout, &rule_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+        // Create the transition pipeline
+        let transition_shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("transition.wgsl"),
+            source: wgpu::ShaderSource::Wgsl(options.format.shader_source(
+                &include_str!("transition.wgsl").replace("{workgroup_size}", &options.workgroup_size.to_string()),
+            ).into()),
+        });
+        let transition_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("compute_pipeline"),
+            layout: Some(&transition_pipeline_layout),
+            module: &transition_shader_module,
+            entry_point: "main",
+            compilation_options: Default::default(),
+        });
+        let blocked_pipeline = (options.block_generations > 1).then(|| {
+            let constants = [("BLOCK_GENERATIONS".to_string(), options.block_generations as f64)].into();
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("blocked_compute_pipeline"),
+                layout: Some(&transition_pipeline_layout),
+                module: &transition_shader_module,
+                entry_point: "main_blocked",
+                compilation_options: wgpu::PipelineCompilationOptions {
+                    constants: &constants,
+                    ..Default::default()
+                },
+            })
+        });
+
+        // Create the soup pipeline. It uses the transition bind groups and only writes to
+        // the output texture.
+        let soup_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("soup_pipeline_layout"),
+            bind_group_layouts: &[&transition_bind_group_layout, &uniform_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let soup_shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("random.wgsl"),
+            source: wgpu::ShaderSource::Wgsl(options.format.shader_source(include_str!("random.wgsl")).into()),
+        });
+        let soup_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("soup_pipeline"),
+            layout: Some(&soup_pipeline_layout),
+            module: &soup_shader_module,
+            entry_point: "main",
+            compilation_options: Default::default(),
+        });
+
+        Pipelines {
+            transition_bind_group_layout,
+            rule_bind_group_layout,
+            uniform_bind_group_layout,
+            transition_shader_module,
+            transition_pipeline,
+            blocked_pipeline,
+            soup_pipeline,
+            bounds_pipeline: OnceLock::new(),
+        }
+    }
+}
+
+/// Owns the device and the ping-pong textures and advances the automaton without
+/// needing a window. Renderers sample the textures through `texture_view`.
+pub struct Simulation {
+    adapter: Arc<wgpu::Adapter>,
+    device: Arc<wgpu::Device>,
+    queue: Arc<wgpu::Queue>,
+    texture_size: (u32, u32),
+    format: CellFormat,
+    rule: Rule,
+    boundary: Boundary,
+    generation: u64,
+
+    texture_swapper: TextureSwapper,
+    // Shared with the other chunks of a `World`
+    pipelines: Arc<Pipelines>,
+    block_generations: u32,
+    workgroup_size: u32,
+    rule_buffer: wgpu::Buffer,
+    boundary_buffer: wgpu::Buffer,
+    // Binds the rule and boundary uniforms
+    rule_bind_group: wgpu::BindGroup,
+    soup_buffer: wgpu::Buffer,
+    soup_bind_group: wgpu::BindGroup,
+    bit_kernel: Option<BitKernel>,
+    activity_tracker: Option<ActivityTracker>,
+    // Created on first use by `encode_live_bounds`
+    bounds_finder: Option<BoundsFinder>,
+    // Set when the textures were written and the bit kernel words are out of date
+    words_stale: bool,
+    // Kept to recreate the simulation after the device is lost
+    options: SimulationOptions,
+    device_lost: Arc<AtomicBool>,
+    error: Arc<Mutex<Option<wgpu::Error>>>,
+    // The last grid read back by `take_snapshot` and its generation
+    snapshot: Option<(Grid, u64)>,
+    // The snapshot being read back, see `poll_snapshot`
+    pending_snapshot: Option<Readback>,
+    // Set when the cells changed since the last snapshot was started
+    snapshot_stale: bool,
+}
+
+// A copy of the current generation on its way to the CPU
+struct Readback {
+    staging_buffer: wgpu::Buffer,
+    size: (u32, u32),
+    padded_bytes_per_row: u32,
+    generation: u64,
+    // Set by the map callback
+    mapping: Arc<Mutex<Mapping>>,
+    // Destroying the device can map the buffer after destroying it
+    device_lost: Arc<AtomicBool>,
+}
+
+// The result of mapping a readback, and the task waiting for it
+#[derive(Default)]
+struct Mapping {
+    result: Option<Result<(), wgpu::BufferAsyncError>>,
+    waker: Option<Waker>,
+}
+
+// Passed to `map_async`. Dropping it without calling it, as happens when the device is
+// lost, fails the mapping so nothing waits for it forever.
+struct MapCallback(Arc<Mutex<Mapping>>);
+
+impl MapCallback {
+    fn call(self, result: Result<(), wgpu::BufferAsyncError>) {
+        self.0.lock().unwrap().result = Some(result);
+    }
+}
+
+impl Drop for MapCallback {
+    fn drop(&mut self) {
+        let waker = {
+            let mut mapping = self.0.lock().unwrap();
+            mapping.result.get_or_insert(Err(wgpu::BufferAsyncError));
+            mapping.waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl Readback {
+    // The cells once the copy is mapped, None while it is in flight
+    fn try_finish(&self, format: CellFormat) -> Option<Result<Grid, wgpu::BufferAsyncError>> {
+        let mut mapped = self.mapping.lock().unwrap().result.clone()?;
+        if self.device_lost.load(Ordering::Acquire) {
+            mapped = Err(wgpu::BufferAsyncError);
+        }
+        Some(mapped.map(|()| {
+            let (width, height) = self.size;
+            let unpadded_bytes_per_row = (width * format.bytes_per_cell()) as usize;
+
+            // Strip the row padding
+            let mut cells = Vec::with_capacity(width as usize * height as usize);
+            {
+                let data = self.staging_buffer.slice(..).get_mapped_range();
+                for row in data.chunks_exact(self.padded_bytes_per_row as usize) {
+                    cells.extend(format.decode(&row[..unpadded_bytes_per_row]));
+                }
+            }
+            self.staging_buffer.unmap();
+            Grid::from_cells(width, height, cells)
+        }))
+    }
+
+    // Like `try_finish`, waking `waker` once the copy is mapped while it is in flight
+    fn poll_finish(&self, format: CellFormat, waker: &Waker) -> Option<Result<Grid, wgpu::BufferAsyncError>> {
+        {
+            let mut mapping = self.mapping.lock().unwrap();
+            if mapping.result.is_none() {
+                mapping.waker = Some(waker.clone());
+                return None;
+            }
+        }
+        self.try_finish(format)
+    }
+}
+
+/// The cells of a generation being copied back to the CPU, see `Simulation::read_cells`
+pub struct ReadCells {
+    readback: Readback,
+    format: CellFormat,
+}
+
+impl Future for ReadCells {
+    type Output = Result<Grid, wgpu::BufferAsyncError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.readback.poll_finish(self.format, cx.waker()) {
+            Some(result) => Poll::Ready(result),
+            None => Poll::Pending,
+        }
+    }
+}
+
+impl Simulation {
+    /// Creates a simulation on a new instance with a dead grid
+    pub async fn new(options: &SimulationOptions) -> Result<Simulation, SimulationError> {
+        let instance = wgpu::Instance::default();
+        Simulation::with_instance(&instance, None, options).await
+    }
+
+    /// Creates a simulation on an adapter of `instance` able to present to
+    /// `compatible_surface`, if one is given
+    pub async fn with_instance(
+        instance: &wgpu::Instance,
+        compatible_surface: Option<&wgpu::Surface<'_>>,
+        options: &SimulationOptions,
+    ) -> Result<Simulation, SimulationError> {
+        // Fail before requesting an adapter when no adapter could run the options
+        check_options(options)?;
+        let gpu = Gpu::request(instance, compatible_surface, options).await?;
+        Simulation::on_gpu(gpu, options)
+    }
+
+    // Creates a simulation on an existing device, see `World`
+    pub(crate) fn on_gpu(gpu: Gpu, options: &SimulationOptions) -> Result<Simulation, SimulationError> {
+        check_options(options)?;
+        let pipelines = Arc::new(Pipelines::new(&gpu.device, options));
+        Simulation::with_pipelines(gpu, pipelines, options)
+    }
+
+    // Creates a simulation on an existing device with pipelines created for the same
+    // format, workgroups and block generations, see `Pipelines`
+    pub(crate) fn with_pipelines(
+        gpu: Gpu,
+        pipelines: Arc<Pipelines>,
+        options: &SimulationOptions,
+    ) -> Result<Simulation, SimulationError> {
+        check_options(options)?;
+        let max = gpu.device.limits().max_texture_dimension_2d;
+        let (width, height) = options.size;
+        if width == 0 || height == 0 || width.max(height) > max {
+            return Err(SimulationError::InvalidSize { size: options.size, max });
+        }
+        let Gpu { adapter, device, queue, device_lost, error } = gpu;
+
+        // Create rule uniform
+        let rule_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("rule_buffer"),
+            size: std::mem::size_of::<Rule>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        // Upload the rule
+        queue.write_buffer(&rule_buffer, 0, bytemuck::bytes_of(&options.rule));
+
+        // Create and upload the boundary uniform
+        let boundary_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("boundary_buffer"),
+            size: std::mem::size_of::<BoundaryUniform>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        queue.write_buffer(&boundary_buffer, 0, bytemuck::bytes_of(&options.boundary.uniform()));
+
+        // Create rule bind group, which also holds the boundary
+        let rule_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &pipelines.rule_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer: &rule_buffer,
+                        offset: 0,
+                        size: None,
+                    }),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: boundary_buffer.as_entire_binding(),
+                },
+            ],
+            label: Some("rule_bind_group"),
+        });
+
+        // Create the soup uniform
+        let soup_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("soup_buffer"),
+            size: std::mem::size_of::<SoupUniform>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let soup_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &pipelines.uniform_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: soup_buffer.as_entire_binding(),
+                }
+            ],
+            label: Some("soup_bind_group"),
+        });
+
+        // Create the textures and the kernels sized to the grid
+        let (texture_swapper, activity_tracker, bit_kernel) =
+            Simulation::create_grid_resources(&device, &queue, options, &pipelines);
+
+        Ok(Self {
+            adapter,
+            device,
+            queue,
+            texture_size: options.size,
+            format: options.format,
+            rule: options.rule,
+            boundary: options.boundary,
+            generation: 0,
+            texture_swapper,
+            pipelines,
+            block_generations: options.block_generations,
+            workgroup_size: options.workgroup_size,
+            rule_buffer,
+            boundary_buffer,
+            rule_bind_group,
+            soup_buffer,
+            soup_bind_group,
+            bit_kernel,
+            activity_tracker,
+            bounds_finder: None,
+            words_stale: true,
+            options: options.clone(),
+            device_lost,
+            error,
+            snapshot: None,
+            pending_snapshot: None,
+            snapshot_stale: true,
+        })
+    }
+
+    // Creates the textures, the activity tracker and the bit kernel for a grid of
+    // `options.size`
+    fn create_grid_resources(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        options: &SimulationOptions,
+        pipelines: &Pipelines,
+    ) -> (TextureSwapper, Option<ActivityTracker>, Option<BitKernel>) {
+        // Create the textures
+        let (texture_a, view_a) = Simulation::create_texture(device, options.size, options.format);
+        let (texture_b, view_b) = Simulation::create_texture(device, options.size, options.format);
+
+        // Create bindings for reading and writing to textures
+        let layout = &pipelines.transition_bind_group_layout;
+        let bind_group_a = Simulation::create_transition_bind_group(device, &view_a, &view_b, layout);
+        let bind_group_b = Simulation::create_transition_bind_group(device, &view_b, &view_a, layout);
+
+        let activity_tracker = (options.skip_stable_tiles && options.kernel == Kernel::Texture).then(|| {
+            ActivityTracker::new(
+                device,
+                queue,
+                options.size,
+                options.workgroup_size - 2,
+                &pipelines.transition_shader_module,
+                &pipelines.transition_bind_group_layout,
+                &pipelines.rule_bind_group_layout,
+            )
+        });
+
+        let bit_kernel = match options.kernel {
+            Kernel::Texture => None,
+            Kernel::BitPacked => Some(BitKernel::new(
+                device,
+                queue,
+                options.size,
+                options.format,
+                &pipelines.transition_bind_group_layout,
+                &pipelines.rule_bind_group_layout,
+            )),
+        };
+
+        // Create the texture swapper
+        let texture_swapper = TextureSwapper {
+            index: 0,
+            texture_resources: [
+                TextureResource { texture: texture_a, view: view_a, transition_bind_group: bind_group_a },
+                TextureResource { texture: texture_b, view: view_b, transition_bind_group: bind_group_b },
+            ],
+        };
+
+        (texture_swapper, activity_tracker, bit_kernel)
+    }
+
+    fn create_texture(device: &wgpu::Device, (width, height): (u32, u32), format: CellFormat) -> (wgpu::Texture, wgpu::TextureView) {
+        let texture = device.create_texture(
+            &wgpu::TextureDescriptor{
+                label: Some("texture"),
+                size: wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: format.texture_format(),
+                usage: wgpu::TextureUsages::TEXTURE_BINDING
+                    | wgpu::TextureUsages::STORAGE_BINDING
+                    | wgpu::TextureUsages::COPY_SRC
+                    | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            }
+        );
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        (texture, view)
+    }
+
+    fn create_transition_bind_group(
+        device: &wgpu::Device,
+        read: &wgpu::TextureView,
+        write: &wgpu::TextureView,
+        layout: &wgpu::BindGroupLayout
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(
+            &wgpu::BindGroupDescriptor {
+                layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(read),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(write),
+                    },
+                ],
+                label: Some("transition_bind_group"),
+            }
+        )
+    }
+
+    fn compute_work_group_count(
+        (width, height): (u32, u32),
+        (workgroup_width, workgroup_height): (u32, u32),
+    ) -> (u32, u32) {
+        let x = width.div_ceil(workgroup_width);
+        let y = height.div_ceil(workgroup_height);
+
+        (x, y)
+    }
+
+    /// The adapter the device was created on
+    pub fn adapter(&self) -> &wgpu::Adapter {
+        &self.adapter
+    }
+
+    /// The device owning the textures, for recording work that uses them
+    pub fn device(&self) -> &wgpu::Device {
+        &self.device
+    }
+
+    /// The queue of the device
+    pub fn queue(&self) -> &wgpu::Queue {
+        &self.queue
+    }
+
+    /// Width and height of the grid in cells
+    pub fn size(&self) -> (u32, u32) {
+        self.texture_size
+    }
+
+    /// How the cells are stored in the textures
+    pub fn format(&self) -> CellFormat {
+        self.format
+    }
+
+    /// Generations advanced since the simulation was created or reset
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Whether the device was lost and `recover` must be called before stepping
+    pub fn is_device_lost(&self) -> bool {
+        self.device_lost.load(Ordering::Acquire)
+    }
+
+    /// Takes the first wgpu error raised since the last call, such as running out of
+    /// memory, while the device was alive. The work that failed had no effect.
+    pub fn take_error(&self) -> Option<wgpu::Error> {
+        self.error.lock().unwrap().take()
+    }
+
+    /// Starts reading back the current generation for `recover`, unless the cells did
+    /// not change since the last snapshot or one is still being read back. The copy
+    /// completes in the background, see `poll_snapshot`.
+    pub fn take_snapshot(&mut self) {
+        if self.snapshot_stale && self.pending_snapshot.is_none() && !self.is_device_lost() {
+            self.pending_snapshot = Some(self.start_readback());
+            self.snapshot_stale = false;
+        }
+    }
+
+    /// Keeps the snapshot started by `take_snapshot` once its copy is done, without
+    /// blocking. Returns whether it is still being read back.
+    pub fn poll_snapshot(&mut self) -> bool {
+        let Some(readback) = &self.pending_snapshot else {
+            return false;
+        };
+        self.device.poll(wgpu::Maintain::Poll);
+        match readback.try_finish(self.format) {
+            None => return true,
+            Some(Ok(grid)) => self.snapshot = Some((grid, readback.generation)),
+            // Try again with the next snapshot
+            Some(Err(_)) => self.snapshot_stale = true,
+        }
+        self.pending_snapshot = None;
+        false
+    }
+
+    /// Recreates the device and everything on it after the device was lost, restoring
+    /// the last snapshot with the current rule and boundary. Without a snapshot the grid
+    /// starts out dead. On error the simulation is left as it was and recovering can be
+    /// tried again.
+    pub async fn recover(
+        &mut self,
+        instance: &wgpu::Instance,
+        compatible_surface: Option<&wgpu::Surface<'_>>,
+    ) -> Result<(), SimulationError> {
+        let gpu = Gpu::request(instance, compatible_surface, &self.options).await?;
+        let pipelines = Arc::new(Pipelines::new(&gpu.device, &self.options));
+        self.recover_on(gpu, pipelines)
+    }
+
+    // Recreates the simulation on a new device, see `recover`
+    pub(crate) fn recover_on(&mut self, gpu: Gpu, pipelines: Arc<Pipelines>) -> Result<(), SimulationError> {
+        let options = SimulationOptions {
+            rule: self.rule,
+            boundary: self.boundary,
+            ..self.options.clone()
+        };
+        let mut simulation = Simulation::with_pipelines(gpu, pipelines, &options)?;
+        if let Some((grid, generation)) = self.snapshot.take() {
+            simulation.set_grid(&grid);
+            simulation.generation = generation;
+            simulation.snapshot = Some((grid, generation));
+            simulation.snapshot_stale = false;
+        }
+        *self = simulation;
+        Ok(())
+    }
+
+    // The texture holding the current generation and the one the next generation is
+    // written to, see `World`
+    pub(crate) fn read_texture(&self) -> &wgpu::Texture {
+        &self.texture_swapper.get_read_resource().texture
+    }
+
+    pub(crate) fn write_texture(&self) -> &wgpu::Texture {
+        &self.texture_swapper.get_write_resource().texture
+    }
+
+    // Records finding the live cells of the current generation more than `margin` cells
+    // from the edges, see `BoundsFinder::encode`. Only one search can be recorded per
+    // submission and the margin of the first one is kept.
+    pub(crate) fn encode_live_bounds(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        margin: u32,
+        target: &wgpu::Buffer,
+        offset: u64,
+    ) {
+        let pipelines = &self.pipelines;
+        let pipeline = pipelines.bounds_pipeline.get_or_init(|| {
+            BoundsPipeline::new(&self.device, self.format, &pipelines.transition_bind_group_layout)
+        });
+        let bounds_finder = self.bounds_finder.get_or_insert_with(|| BoundsFinder::new(&self.device, &self.queue, pipeline, margin));
+        let textures = &self.texture_swapper.get_read_resource().transition_bind_group;
+        bounds_finder.encode(pipeline, encoder, textures, self.texture_size, target, offset);
+    }
+
+    /// Index of the texture holding the current generation, see `texture_view`
+    pub fn read_index(&self) -> usize {
+        self.texture_swapper.index
+    }
+
+    /// View of one of the two cell textures
+    pub fn texture_view(&self, index: usize) -> &wgpu::TextureView {
+        &self.texture_swapper.texture_resources[index].view
+    }
+
+    /// The rule used by the next generation
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    /// Switches the rule used by the following generations
+    pub fn set_rule(&mut self, rule: Rule) {
+        self.rule = rule;
+
+        // Upload the rule
+        self.queue.write_buffer(&self.rule_buffer, 0, bytemuck::bytes_of(&self.rule));
+
+        // Tiles that were stable under the old rule may not be under the new one
+        self.grid_written();
+    }
+
+    /// Switches how the edges are joined for the following generations
+    pub fn set_boundary(&mut self, boundary: Boundary) {
+        self.boundary = boundary;
+        self.queue.write_buffer(&self.boundary_buffer, 0, bytemuck::bytes_of(&boundary.uniform()));
+
+        // The cells along the edges see different neighbors
+        self.grid_written();
+    }
+
+    /// Changes the size of the grid, keeping the current generation where `anchor` puts
+    /// it. The textures are recreated, so views from `texture_view` must be fetched
+    /// again. On error the simulation is left as it was.
+    pub fn resize(&mut self, size: (u32, u32), anchor: Anchor) -> Result<(), SimulationError> {
+        let (width, height) = size;
+        let max = self.device.limits().max_texture_dimension_2d;
+        if width == 0 || height == 0 || width.max(height) > max {
+            return Err(SimulationError::InvalidSize { size, max });
+        }
+        if self.options.kernel == Kernel::BitPacked && !width.is_multiple_of(32) {
+            return Err(SimulationError::BitKernelWidth(width));
+        }
+
+        let options = SimulationOptions { size, ..self.options.clone() };
+        let (texture_swapper, activity_tracker, bit_kernel) = Simulation::create_grid_resources(
+            &self.device,
+            &self.queue,
+            &options,
+            &self.pipelines,
+        );
+
+        // Copy the current generation into the new read texture
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("resize_encoder"),
+        });
+        let source = &self.texture_swapper.get_read_resource().texture;
+        let target = &texture_swapper.get_read_resource().texture;
+        for ((source_x, source_y), (target_x, target_y), (columns, rows)) in resize_regions(self.texture_size, size, anchor) {
+            encoder.copy_texture_to_texture(
+                wgpu::ImageCopyTexture {
+                    texture: source,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d { x: source_x, y: source_y, z: 0 },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                wgpu::ImageCopyTexture {
+                    texture: target,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d { x: target_x, y: target_y, z: 0 },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                wgpu::Extent3d {
+                    width: columns,
+                    height: rows,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+        self.queue.submit([encoder.finish()]);
+
+        self.texture_swapper = texture_swapper;
+        self.activity_tracker = activity_tracker;
+        self.bit_kernel = bit_kernel;
+        self.texture_size = size;
+        self.options = options;
+        self.snapshot = self.snapshot.take().map(|(grid, generation)| (grid.resized(size, anchor), generation));
+        self.pending_snapshot = None;
+        self.grid_written();
+        Ok(())
+    }
+
+    /// Records the given number of generations into the encoder. The texture swapper is
+    /// swapped immediately, so the encoder must be submitted before any later transition.
+    pub fn transition(&mut self, encoder: &mut wgpu::CommandEncoder, generations: u32) {
+        // The bit-packed kernel only runs on the torus, other boundaries fall back to the
+        // texture kernel
+        if let Some(bit_kernel) = self.bit_kernel.as_mut().filter(|_| self.boundary == Boundary::Torus) {
+            if generations == 0 {
+                return;
+            }
+
+            // Step the words and unpack the result once, so textures are only touched
+            // when the grid was written and for display
+            let texture_resource = self.texture_swapper.get_read_resource();
+            let textures = &texture_resource.transition_bind_group;
+            if self.words_stale {
+                bit_kernel.encode_pack(encoder, textures);
+                self.words_stale = false;
+            }
+            bit_kernel.encode_steps(encoder, generations, &self.rule_bind_group);
+            bit_kernel.encode_unpack(encoder, textures);
+            self.texture_swapper.swap();
+            self.generation += generations as u64;
+            self.snapshot_stale = true;
+            return;
+        }
+
+        let tile_size = self.workgroup_size - 2;
+        let (dispatch_with, dispatch_height) = Simulation::compute_work_group_count(
+            self.texture_size,
+            (tile_size, tile_size)
+        );
+        // The tiles are two cells narrower than the work groups because the threads around the
+        // edges are only used for populating the shared memory.
+
+        // The textures move ahead of the bit kernel words
+        if self.bit_kernel.is_some() && generations > 0 {
+            self.words_stale = true;
+        }
+        self.snapshot_stale |= generations > 0;
+
+        // Whole blocks first, the remaining generations one dispatch each. The cross-surface
+        // is not made of images of the grid that evolve like it, so it is not blocked.
+        let mut generations = generations;
+        if let Some(blocked_pipeline) = self.pipelines.blocked_pipeline.as_ref().filter(|_| self.boundary != Boundary::Cross) {
+            let stride = self.workgroup_size - 2 * self.block_generations;
+            let (blocks_width, blocks_height) = Simulation::compute_work_group_count(
+                self.texture_size,
+                (stride, stride),
+            );
+            while generations >= self.block_generations {
+                {
+                    let texture_resource = self.texture_swapper.get_read_resource();
+                    let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor{
+                        label: Some("blocked_compute_pass"),
+                        timestamp_writes: None,
+                    });
+
+                    compute_pass.set_pipeline(blocked_pipeline);
+                    compute_pass.set_bind_group(0, &texture_resource.transition_bind_group, &[]);
+                    compute_pass.set_bind_group(1, &self.rule_bind_group, &[]);
+                    compute_pass.dispatch_workgroups(blocks_width, blocks_height, 1);
+                }
+
+                self.texture_swapper.swap();
+                self.generation += self.block_generations as u64;
+                generations -= self.block_generations;
+            }
+        }
+
+        for _ in 0..generations {
+            if let Some(activity_tracker) = &mut self.activity_tracker {
+                let texture_resource = self.texture_swapper.get_read_resource();
+                activity_tracker.encode_generation(
+                    encoder,
+                    &texture_resource.transition_bind_group,
+                    &self.rule_bind_group,
+                );
+            } else {
+                let texture_resource = self.texture_swapper.get_read_resource();
+                let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor{
+                    label: Some("transition_compute_pass"),
+                    timestamp_writes: None,
+                });
+
+                compute_pass.set_pipeline(&self.pipelines.transition_pipeline);
+                compute_pass.set_bind_group(0, &texture_resource.transition_bind_group, &[]);
+                compute_pass.set_bind_group(1, &self.rule_bind_group, &[]);
+                compute_pass.dispatch_workgroups(dispatch_with, dispatch_height, 1);
+            }
+
+            self.texture_swapper.swap();
+            self.generation += 1;
+        }
+    }
+
+    /// Advances the simulation by the given number of generations in a single submission
+    pub fn step(&mut self, generations: u32) {
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("transition_encoder"),
+        });
+        self.transition(&mut encoder, generations);
+        self.queue.submit([encoder.finish()]);
+    }
+
+    // Called whenever the textures are written outside the transition
+    fn grid_written(&mut self) {
+        self.words_stale = true;
+        self.snapshot_stale = true;
+        if let Some(activity_tracker) = &mut self.activity_tracker {
+            activity_tracker.mark_all_changed();
+        }
+    }
+
+    /// Replaces the grid with texel data in the layout of `format`, see `CellFormat::encode`
+    pub fn set_texture(&mut self, data: &[u8]) {
+        self.grid_written();
+        let texture = &self.texture_swapper.get_write_resource().texture;
+
+        self.queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            data,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(self.texture_size.0 * self.format.bytes_per_cell()),
+                rows_per_image: Some(self.texture_size.1),
+            },
+            wgpu::Extent3d {
+                width: self.texture_size.0,
+                height: self.texture_size.1,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        self.texture_swapper.swap();
+    }
+
+    /// Copies the current generation back to the CPU. The future completes once the copy
+    /// is mapped, which on native only happens while the device is polled, e.g. with
+    /// `device().poll(wgpu::Maintain::Wait)` or once per frame. Fails when the device is
+    /// lost before the copy finishes.
+    pub fn read_cells(&self) -> ReadCells {
+        ReadCells { readback: self.start_readback(), format: self.format }
+    }
+
+    /// Like `read_cells`, blocking until the copy is done
+    pub fn read_cells_blocking(&self) -> Result<Grid, wgpu::BufferAsyncError> {
+        let readback = self.start_readback();
+        self.device.poll(wgpu::Maintain::Wait);
+        // A lost device may never run the callback
+        readback.try_finish(self.format).unwrap_or(Err(wgpu::BufferAsyncError))
+    }
+
+    // Copies the current generation into a staging buffer and starts mapping it
+    fn start_readback(&self) -> Readback {
+        let (width, height) = self.texture_size;
+        let texture = &self.texture_swapper.get_read_resource().texture;
+
+        // Rows copied into a buffer must be padded to COPY_BYTES_PER_ROW_ALIGNMENT
+        let padded_bytes_per_row = (width * self.format.bytes_per_cell()).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
+        let staging_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("readback_buffer"),
+            size: padded_bytes_per_row as u64 * height as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("readback_encoder"),
+        });
+
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &staging_buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        self.queue.submit([encoder.finish()]);
+
+        // The buffer is mapped once the copy is done
+        let mapping = Arc::new(Mutex::new(Mapping::default()));
+        let callback = MapCallback(Arc::clone(&mapping));
+        staging_buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| callback.call(result));
+
+        Readback {
+            staging_buffer,
+            size: self.texture_size,
+            padded_bytes_per_row,
+            generation: self.generation,
+            mapping,
+            device_lost: Arc::clone(&self.device_lost),
+        }
+    }
+
+    /// Overwrites a region of the current generation in place. The region wraps around
+    /// the edges of the torus, so it is uploaded in up to four pieces.
+    pub fn set_region(&mut self, (x, y): (u32, u32), grid: &Grid) {
+        self.grid_written();
+        let texture = &self.texture_swapper.get_read_resource().texture;
+        let (width, height) = self.texture_size;
+        let cell_size = self.format.bytes_per_cell();
+        let data = self.format.encode(grid.cells());
+
+        for (source_y, target_y, rows) in wrapped_spans(y, grid.height().min(height), height) {
+            for (source_x, target_x, columns) in wrapped_spans(x, grid.width().min(width), width) {
+                self.queue.write_texture(
+                    wgpu::ImageCopyTexture {
+                        texture,
+                        mip_level: 0,
+                        origin: wgpu::Origin3d { x: target_x, y: target_y, z: 0 },
+                        aspect: wgpu::TextureAspect::All,
+                    },
+                    &data,
+                    wgpu::ImageDataLayout {
+                        offset: ((source_y * grid.width() + source_x) * cell_size) as u64,
+                        bytes_per_row: Some(grid.width() * cell_size),
+                        rows_per_image: Some(grid.height()),
+                    },
+                    wgpu::Extent3d {
+                        width: columns,
+                        height: rows,
+                        depth_or_array_layers: 1,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Overwrites a single cell of the current generation
+    pub fn set_cell(&mut self, position: (u32, u32), cell: Cell) {
+        self.set_region(position, &Grid::from_cells(1, 1, vec![cell]));
+    }
+
+    /// Overwrites the region of the pattern with its top left corner at `origin`
+    pub fn place_pattern(&mut self, origin: (u32, u32), pattern: &Pattern) {
+        self.set_region(origin, &pattern.to_grid());
+    }
+
+    /// Places the pattern in the middle of the grid
+    pub fn center_pattern(&mut self, pattern: &Pattern) {
+        let (width, height) = self.texture_size;
+        let origin = (
+            width.saturating_sub(pattern.width()) / 2,
+            height.saturating_sub(pattern.height()) / 2,
+        );
+        self.place_pattern(origin, pattern);
+    }
+
+    /// Reads back the whole grid, or the (origin, size) selection, as a pattern tagged
+    /// with the rule being simulated, see `read_cells`
+    pub fn export_pattern(&self, selection: Option<((u32, u32), (u32, u32))>) -> Result<Pattern, wgpu::BufferAsyncError> {
+        let grid = self.read_cells_blocking()?;
+        let (origin, size) = selection.unwrap_or(((0, 0), self.texture_size));
+        let mut pattern = Pattern::from_grid(&grid, origin, size);
+        pattern.set_rule(Some(self.rule));
+        Ok(pattern)
+    }
+
+    /// Replaces the whole grid, which must match the simulation size
+    pub fn set_grid(&mut self, grid: &Grid) {
+        assert_eq!((grid.width(), grid.height()), self.texture_size, "grid size does not match simulation size");
+        self.set_texture(&self.format.encode(grid.cells()));
+    }
+
+    /// Kills every cell and clears the trails, starting over at generation zero
+    pub fn reset(&mut self) {
+        let (width, height) = self.texture_size;
+        self.set_grid(&Grid::new(width, height));
+        self.generation = 0;
+        self.snapshot = None;
+        self.pending_snapshot = None;
+    }
+
+    /// Fills the grid using the generator, `density` being the chance of a random cell
+    /// being alive. The same generator, seed and density always produce the same grid.
+    pub fn randomize(&mut self, generator: Generator, seed: u64, density: f64) {
+        match generator {
+            Generator::Soup => self.randomize_soup(seed, density, (0, 0), self.texture_size),
+            _ => self.set_grid(&generator.generate(self.texture_size, seed, density)),
+        }
+    }
+
+    // Fills the write texture with the same soup as `Generator::Soup` without building
+    // it on the CPU, then swaps it in like `set_texture`. The textures hold the part of
+    // a soup of `world_size` starting at `origin`, see `World`.
+    pub(crate) fn randomize_soup(&mut self, seed: u64, density: f64, origin: (u32, u32), world_size: (u32, u32)) {
+        self.grid_written();
+        let soup = SoupUniform::new(seed, density, origin, world_size);
+        self.queue.write_buffer(&self.soup_buffer, 0, bytemuck::bytes_of(&soup));
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("soup_encoder"),
+        });
+        {
+            let (dispatch_width, dispatch_height) = Simulation::compute_work_group_count(self.texture_size, (16, 16));
+            let texture_resource = self.texture_swapper.get_read_resource();
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor{
+                label: Some("soup_compute_pass"),
+                timestamp_writes: None,
+            });
+
+            compute_pass.set_pipeline(&self.pipelines.soup_pipeline);
+            compute_pass.set_bind_group(0, &texture_resource.transition_bind_group, &[]);
+            compute_pass.set_bind_group(1, &self.soup_bind_group, &[]);
+            compute_pass.dispatch_workgroups(dispatch_width, dispatch_height, 1);
+        }
+        self.queue.submit([encoder.finish()]);
+        self.texture_swapper.swap();
+    }
+}
+
+// Splits a span of `length` cells starting at `start` on a torus of size `size` into
+// (offset in span, position on torus, length) pieces that do not cross the edge.
+pub(crate) fn wrapped_spans(start: u32, length: u32, size: u32) -> impl Iterator<Item = (u32, u32, u32)> {
+    let start = start % size;
+    let first = length.min(size - start);
+    [(0, start, first), (first, 0, length - first)]
+        .into_iter()
+        .filter(|&(_, _, length)| length > 0)
+}
+
+// Checks the options that do not depend on the adapter
+fn check_options(options: &SimulationOptions) -> Result<(), SimulationError> {
+    if options.block_generations == 0
+        || options.block_generations.checked_mul(2).is_none_or(|twice| twice >= options.workgroup_size)
+    {
+        return Err(SimulationError::BlockGenerations {
+            block_generations: options.block_generations,
+            workgroup_size: options.workgroup_size,
+        });
+    }
+    if options.block_generations > 1 && (options.kernel != Kernel::Texture || options.skip_stable_tiles) {
+        return Err(SimulationError::BlockedKernel);
+    }
+    if options.kernel == Kernel::BitPacked && !options.size.0.is_multiple_of(32) {
+        return Err(SimulationError::BitKernelWidth(options.size.0));
+    }
+    Ok(())
+}
+
+// A rectangle copied between textures as (source origin, target origin, size)
+type CopyRegion = ((u32, u32), (u32, u32), (u32, u32));
+
+// The rectangles copied when a grid is resized from `old` to `new`, see `Anchor`
+fn resize_regions(old: (u32, u32), new: (u32, u32), anchor: Anchor) -> Vec<CopyRegion> {
+    // The (source, target, length) spans along one axis
+    let spans = |old: u32, new: u32, offset: i64| -> Vec<(u32, u32, u32)> {
+        match anchor {
+            Anchor::Tiled => (0..new).step_by(old as usize).map(|start| (0, start, old.min(new - start))).collect(),
+            Anchor::Center | Anchor::Origin => {
+                let source = (-offset).max(0) as u32;
+                let target = offset.max(0) as u32;
+                vec![(source, target, (old - source).min(new - target))]
+            },
+        }
+    };
+    let (offset_x, offset_y) = anchor.offset(old, new);
+    let columns = spans(old.0, new.0, offset_x);
+    let rows = spans(old.1, new.1, offset_y);
+    rows.iter()
+        .flat_map(|&(source_y, target_y, height)| {
+            columns.iter().map(move |&(source_x, target_x, width)| ((source_x, source_y), (target_x, target_y), (width, height)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reference::{first_difference, step_n};
+    use crate::testing::{alive_cells, gpu_simulation, gpu_simulation_if_supported, gpu_simulation_with, grid_with, GLIDER};
+
+    #[test]
+    fn invalid_options_are_errors() {
+        let create = |options| pollster::block_on(Simulation::new(&options)).err();
+        assert!(matches!(
+            create(SimulationOptions { size: (40, 32), kernel: Kernel::BitPacked, ..SimulationOptions::default() }),
+            Some(SimulationError::BitKernelWidth(40)),
+        ));
+        assert!(matches!(
+            create(SimulationOptions { block_generations: 4, workgroup_size: 8, ..SimulationOptions::default() }),
+            Some(SimulationError::BlockGenerations { block_generations: 4, workgroup_size: 8 }),
+        ));
+        assert!(matches!(
+            create(SimulationOptions { block_generations: 2, skip_stable_tiles: true, ..SimulationOptions::default() }),
+            Some(SimulationError::BlockedKernel),
+        ));
+        assert!(matches!(
+            create(SimulationOptions { size: (0, 32), format: CellFormat::Uint32, ..SimulationOptions::default() }),
+            Some(SimulationError::InvalidSize { .. } | SimulationError::NoAdapter),
+        ));
+        assert!(matches!(
+            create(SimulationOptions { workgroup_size: 1 << 12, format: CellFormat::Uint32, ..SimulationOptions::default() }),
+            Some(SimulationError::WorkgroupTooLarge { .. } | SimulationError::NoAdapter),
+        ));
+        assert!(matches!(
+            create(SimulationOptions { workgroup_size: u32::MAX, format: CellFormat::Uint32, ..SimulationOptions::default() }),
+            Some(SimulationError::WorkgroupTooLarge { .. } | SimulationError::NoAdapter),
+        ));
+        assert!(matches!(
+            create(SimulationOptions { block_generations: u32::MAX, workgroup_size: u32::MAX, ..SimulationOptions::default() }),
+            Some(SimulationError::BlockGenerations { .. }),
+        ));
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter, run with --ignored"]
+    fn bit_packed_kernel_matches() {
+        // Patterns crossing the word and grid edges, with uploads between runs
+        let rule: Rule = "B36/S23".parse().unwrap();
+        let glider = grid_with(64, 20, GLIDER, (30, 17));
+        let soup = Generator::Soup.generate((96, 50), 77, 0.4);
+        for (grid, rule, generations) in [(&glider, Rule::CONWAY, 90), (&soup, rule, 60)] {
+            let size = (grid.width(), grid.height());
+            let mut simulation = gpu_simulation_with(SimulationOptions {
+                size,
+                rule,
+                format: CellFormat::Uint32,
+                kernel: Kernel::BitPacked,
+                ..SimulationOptions::default()
+            });
+            simulation.set_grid(grid);
+            simulation.step(generations / 2);
+            simulation.step(0);
+            simulation.set_cell((5, 5), Cell::ALIVE);
+            simulation.step(generations - generations / 2);
+
+            let mut expected = step_n(grid, rule, Boundary::Torus, generations / 2);
+            expected.set(5, 5, Cell::ALIVE);
+            let expected = step_n(&expected, rule, Boundary::Torus, generations - generations / 2);
+            let actual = simulation.read_cells_blocking().unwrap();
+            assert_eq!(first_difference(&expected, &actual), None);
+        }
+    }
+
+    // Returns early when the adapter cannot store `format`
+    fn assert_skipping_stable_tiles_matches(format: CellFormat) {
+        // A soup that settles into still lifes and blinkers while gliders cross the
+        // wrapping edges, on a grid that is not a multiple of the tile size
+        let size = (100, 86);
+        let mut grid = Generator::Disc.generate(size, 3, 0.45);
+        for &(x, y) in GLIDER {
+            grid.set(x + 2, y + 80, Cell::ALIVE);
+        }
+        let Some(mut simulation) = gpu_simulation_if_supported(SimulationOptions {
+            size,
+            format,
+            skip_stable_tiles: true,
+            ..SimulationOptions::default()
+        }) else {
+            return;
+        };
+        simulation.set_grid(&grid);
+        simulation.step(150);
+        simulation.set_cell((50, 2), Cell::ALIVE);
+        simulation.set_rule("B36/S23".parse().unwrap());
+        simulation.step(150);
+
+        let mut expected = step_n(&grid, Rule::CONWAY, Boundary::Torus, 150);
+        expected.set(50, 2, Cell::ALIVE);
+        let expected = step_n(&expected, "B36/S23".parse().unwrap(), Boundary::Torus, 150);
+        let actual = simulation.read_cells_blocking().unwrap();
+        assert_eq!(first_difference(&expected, &actual), None, "{} disagrees with the CPU", format);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter, run with --ignored"]
+    fn skipping_stable_tiles_matches() {
+        assert_skipping_stable_tiles_matches(CellFormat::Uint32);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter storing rg32float cells, run with --ignored"]
+    fn skipping_stable_float_tiles_matches() {
+        assert_skipping_stable_tiles_matches(CellFormat::Float);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter, run with --ignored"]
+    fn skipping_stable_tiles_on_many_tiles() {
+        // Single cell tiles, more than the 65535 workgroups of a dispatch row can compact
+        let size = (2100, 2000);
+        let grid = Generator::Soup.generate(size, 12, 0.3);
+        let mut simulation = gpu_simulation_with(SimulationOptions {
+            size,
+            format: CellFormat::Uint32,
+            skip_stable_tiles: true,
+            workgroup_size: 3,
+            ..SimulationOptions::default()
+        });
+        simulation.set_grid(&grid);
+        simulation.step(2);
+        let actual = simulation.read_cells_blocking().unwrap();
+        assert!(simulation.take_error().is_none());
+        assert_eq!(first_difference(&step_n(&grid, Rule::CONWAY, Boundary::Torus, 2), &actual), None);
+    }
+
+    // Returns early when the adapter cannot store `format`
+    fn assert_blocked_generations_match(format: CellFormat) {
+        // Generation counts that are not multiples of the block, and a grid smaller than
+        // the halo so blocks overlap themselves
+        let soup = Generator::Soup.generate((75, 61), 9, 0.4);
+        let small = grid_with(9, 7, GLIDER, (6, 4));
+        for (grid, block_generations, generations) in [(&soup, 3, 80), (&soup, 7, 45), (&small, 5, 23)] {
+            let size = (grid.width(), grid.height());
+            let run = |block_generations| {
+                let mut simulation = gpu_simulation_if_supported(SimulationOptions {
+                    size,
+                    format,
+                    block_generations,
+                    ..SimulationOptions::default()
+                })?;
+                simulation.set_grid(grid);
+                simulation.step(generations / 2);
+                simulation.step(generations - generations / 2);
+                Some(simulation.read_cells_blocking().unwrap())
+            };
+            let Some(blocked) = run(block_generations) else { return };
+            let unblocked = run(1).unwrap();
+
+            // Float trails decay the same way, integer trails are only quantized when
+            // a block is written back
+            assert_eq!(first_difference(&step_n(grid, Rule::CONWAY, Boundary::Torus, generations), &blocked), None);
+            assert_eq!(first_difference(&blocked, &unblocked), None);
+            if format == CellFormat::Float {
+                assert_eq!(blocked, unblocked);
+            }
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter, run with --ignored"]
+    fn blocked_generations_match() {
+        assert_blocked_generations_match(CellFormat::Uint32);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter storing rg32float cells, run with --ignored"]
+    fn blocked_float_generations_match() {
+        assert_blocked_generations_match(CellFormat::Float);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter, run with --ignored"]
+    fn workgroup_sizes_match() {
+        let grid = Generator::Soup.generate((45, 37), 21, 0.4);
+        let expected = step_n(&grid, Rule::CONWAY, Boundary::Torus, 40);
+        for workgroup_size in [5, 8, 32] {
+            for (skip_stable_tiles, block_generations) in [(false, 1), (true, 1), (false, 2)] {
+                let mut simulation = gpu_simulation_with(SimulationOptions {
+                    size: (grid.width(), grid.height()),
+                    format: CellFormat::Uint32,
+                    skip_stable_tiles,
+                    block_generations,
+                    workgroup_size,
+                    ..SimulationOptions::default()
+                });
+                simulation.set_grid(&grid);
+                simulation.step(40);
+                let actual = simulation.read_cells_blocking().unwrap();
+                assert_eq!(first_difference(&expected, &actual), None, "{}x{} workgroups disagree", workgroup_size, workgroup_size);
+            }
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter, run with --ignored"]
+    fn read_cells_completes_while_the_device_is_polled() {
+        let mut simulation = gpu_simulation((48, 40), Rule::CONWAY, CellFormat::Uint32);
+        simulation.randomize(Generator::Soup, 3, 0.4);
+        let mut context = Context::from_waker(Waker::noop());
+
+        let mut cells = simulation.read_cells();
+        simulation.device().poll(wgpu::Maintain::Wait);
+        let Poll::Ready(grid) = Pin::new(&mut cells).poll(&mut context) else { panic!("the copy was not mapped") };
+        assert_eq!(grid.unwrap(), Generator::Soup.generate((48, 40), 3, 0.4));
+
+        // A lost device fails the readback instead of leaving it pending
+        let mut cells = simulation.read_cells();
+        simulation.device().destroy();
+        simulation.device().poll(wgpu::Maintain::Wait);
+        assert!(matches!(Pin::new(&mut cells).poll(&mut context), Poll::Ready(Err(_))));
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter, run with --ignored"]
+    fn recovers_from_device_loss() {
+        let grid = Generator::Soup.generate((64, 40), 8, 0.4);
+        let mut simulation = gpu_simulation_with(SimulationOptions {
+            size: (64, 40),
+            format: CellFormat::Uint32,
+            kernel: Kernel::BitPacked,
+            ..SimulationOptions::default()
+        });
+        simulation.set_grid(&grid);
+        simulation.step(20);
+        simulation.take_snapshot();
+        simulation.step(10);
+        simulation.device().poll(wgpu::Maintain::Wait);
+        assert!(!simulation.poll_snapshot());
+
+        // Work recorded on the lost device is dropped without panicking
+        simulation.device().destroy();
+        simulation.device().poll(wgpu::Maintain::Wait);
+        assert!(simulation.is_device_lost());
+        simulation.step(5);
+
+        let rule: Rule = "B36/S23".parse().unwrap();
+        simulation.set_rule(rule);
+        pollster::block_on(simulation.recover(&wgpu::Instance::default(), None)).unwrap();
+        assert!(!simulation.is_device_lost());
+        assert_eq!(simulation.generation(), 20);
+        simulation.step(15);
+
+        let expected = step_n(&step_n(&grid, Rule::CONWAY, Boundary::Torus, 20), rule, Boundary::Torus, 15);
+        let actual = simulation.read_cells_blocking().unwrap();
+        assert_eq!(first_difference(&expected, &actual), None);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter, run with --ignored"]
+    fn reset_clears_the_grid() {
+        let mut simulation = gpu_simulation((48, 40), Rule::CONWAY, CellFormat::Uint32);
+        simulation.randomize(Generator::Soup, 5, 0.5);
+        simulation.step(7);
+        simulation.reset();
+        assert_eq!(simulation.generation(), 0);
+        assert!(alive_cells(&simulation.read_cells_blocking().unwrap()).is_empty());
+
+        simulation.set_region((20, 30), &grid_with(3, 3, GLIDER, (0, 0)));
+        simulation.step(12);
+        let expected = step_n(&grid_with(48, 40, GLIDER, (20, 30)), Rule::CONWAY, Boundary::Torus, 12);
+        assert_eq!(first_difference(&expected, &simulation.read_cells_blocking().unwrap()), None);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter, run with --ignored"]
+    fn resizing_keeps_cells() {
+        let grid = Generator::Soup.generate((64, 40), 11, 0.4);
+        let variants = [
+            SimulationOptions { format: CellFormat::Uint32, ..SimulationOptions::default() },
+            SimulationOptions { format: CellFormat::Uint32, skip_stable_tiles: true, ..SimulationOptions::default() },
+            SimulationOptions { format: CellFormat::Uint32, kernel: Kernel::BitPacked, ..SimulationOptions::default() },
+        ];
+        for options in variants {
+            for anchor in [Anchor::Center, Anchor::Origin, Anchor::Tiled] {
+                for size in [(96, 70), (32, 23)] {
+                    let mut simulation = gpu_simulation_with(SimulationOptions { size: (64, 40), ..options.clone() });
+                    simulation.set_grid(&grid);
+                    simulation.step(3);
+                    simulation.resize(size, anchor).unwrap();
+                    assert_eq!(simulation.size(), size);
+
+                    let resized = step_n(&grid, Rule::CONWAY, Boundary::Torus, 3).resized(size, anchor);
+                    let actual = simulation.read_cells_blocking().unwrap();
+                    assert_eq!(first_difference(&resized, &actual), None, "{:?} to {:?}", anchor, size);
+
+                    // The resized kernels keep running on the new grid
+                    simulation.step(9);
+                    let expected = step_n(&resized, Rule::CONWAY, Boundary::Torus, 9);
+                    let actual = simulation.read_cells_blocking().unwrap();
+                    assert_eq!(first_difference(&expected, &actual), None, "{} {:?} to {:?}", options.kernel, anchor, size);
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter, run with --ignored"]
+    fn seeded_runs_are_bit_identical() {
+        let rule: Rule = "B36/S23".parse().unwrap();
+        let run = || {
+            let mut simulation = gpu_simulation((90, 70), rule, CellFormat::Uint32);
+            simulation.randomize(Generator::Soup, 1234, 0.4);
+            let initial = simulation.read_cells_blocking().unwrap();
+            simulation.step(50);
+            (initial, simulation.read_cells_blocking().unwrap())
+        };
+        let (initial, first) = run();
+        let (_, second) = run();
+
+        let bytes = |grid: &Grid| bytemuck::cast_slice::<Cell, u8>(grid.cells()).to_vec();
+        assert_eq!(bytes(&first), bytes(&second));
+        assert_eq!(first_difference(&step_n(&initial, rule, Boundary::Torus, 50), &first), None);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter, run with --ignored"]
+    fn gpu_soup_matches_cpu_soup() {
+        for (size, seed, density) in [((90, 70), 1234, 0.4), ((33, 17), u64::MAX, 0.9), ((20, 20), 0, 1.0)] {
+            let mut simulation = gpu_simulation(size, Rule::CONWAY, CellFormat::Uint32);
+            simulation.randomize(Generator::Soup, seed, density);
+            let actual = simulation.read_cells_blocking().unwrap();
+            assert_eq!(actual, Generator::Soup.generate(size, seed, density));
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use wgpu_automata::{
+    Anchor, Boundary, Cell, CellFormat, Generator, Grid, Pattern, Rule, Simulation, SimulationError, SimulationOptions,
+    Universe, World,
+};
+
+// The cells being simulated: a torus, split into chunks when it is larger than a
+// texture, or an unbounded universe
+pub enum Space {
+    World(World),
+    Universe(Box<Universe>),
+}
+
+// The position and size of the interior of a chunk, and its simulation
+type ChunkView<'a> = ((i64, i64), (u32, u32), &'a Simulation);
+
+impl Space {
+    // Creates the space on an adapter of `instance` able to present to
+    // `compatible_surface`, see `World::with_instance` and `Universe::with_instance`
+    pub async fn with_instance(
+        instance: &wgpu::Instance,
+        compatible_surface: Option<&wgpu::Surface<'_>>,
+        options: &SimulationOptions,
+        chunk_size: Option<u32>,
+        unbounded: bool,
+    ) -> Result<Space, SimulationError> {
+        Ok(match unbounded {
+            false => Space::World(World::with_instance(instance, compatible_surface, options, chunk_size).await?),
+            true => Space::Universe(Box::new(Universe::with_instance(instance, compatible_surface, options, chunk_size).await?)),
+        })
+    }
+
+    pub fn adapter(&self) -> &wgpu::Adapter {
+        match self {
+            Space::World(world) => world.adapter(),
+            Space::Universe(universe) => universe.adapter(),
+        }
+    }
+
+    pub fn device(&self) -> &wgpu::Device {
+        match self {
+            Space::World(world) => world.device(),
+            Space::Universe(universe) => universe.device(),
+        }
+    }
+
+    pub fn queue(&self) -> &wgpu::Queue {
+        match self {
+            Space::World(world) => world.queue(),
+            Space::Universe(universe) => universe.queue(),
+        }
+    }
+
+    pub fn format(&self) -> CellFormat {
+        match self {
+            Space::World(world) => world.format(),
+            Space::Universe(universe) => universe.format(),
+        }
+    }
+
+    pub fn generation(&self) -> u64 {
+        match self {
+            Space::World(world) => world.generation(),
+            Space::Universe(universe) => universe.generation(),
+        }
+    }
+
+    pub fn rule(&self) -> Rule {
+        match self {
+            Space::World(world) => world.rule(),
+            Space::Universe(universe) => universe.rule(),
+        }
+    }
+
+    // The size of the torus, which positions wrap around, or None when unbounded
+    pub fn torus_size(&self) -> Option<(u32, u32)> {
+        match self {
+            Space::World(world) => Some(world.size()),
+            Space::Universe(_) => None,
+        }
+    }
+
+    pub fn halo(&self) -> u32 {
+        match self {
+            Space::World(world) => world.halo(),
+            Space::Universe(universe) => universe.halo(),
+        }
+    }
+
+    pub fn chunks(&self) -> Vec<ChunkView<'_>> {
+        match self {
+            Space::World(world) => world.chunks().map(|((x, y), size, chunk)| ((x as i64, y as i64), size, chunk)).collect(),
+            Space::Universe(universe) => universe.chunks().collect(),
+        }
+    }
+
+    // Changes whenever the chunks change, see `Universe::revision`
+    pub fn revision(&self) -> u64 {
+        match self {
+            Space::World(_) => 0,
+            Space::Universe(universe) => universe.revision(),
+        }
+    }
+
+    pub fn is_device_lost(&self) -> bool {
+        match self {
+            Space::World(world) => world.is_device_lost(),
+            Space::Universe(universe) => universe.is_device_lost(),
+        }
+    }
+
+    pub fn take_error(&self) -> Option<wgpu::Error> {
+        match self {
+            Space::World(world) => world.take_error(),
+            Space::Universe(universe) => universe.take_error(),
+        }
+    }
+
+    pub fn take_snapshot(&mut self) {
+        match self {
+            Space::World(world) => world.take_snapshot(),
+            Space::Universe(universe) => universe.take_snapshot(),
+        }
+    }
+
+    pub fn poll_snapshot(&mut self) -> bool {
+        match self {
+            Space::World(world) => world.poll_snapshot(),
+            Space::Universe(universe) => universe.poll_snapshot(),
+        }
+    }
+
+    pub async fn recover(
+        &mut self,
+        instance: &wgpu::Instance,
+        compatible_surface: Option<&wgpu::Surface<'_>>,
+    ) -> Result<(), SimulationError> {
+        match self {
+            Space::World(world) => world.recover(instance, compatible_surface).await,
+            Space::Universe(universe) => universe.recover(instance, compatible_surface).await,
+        }
+    }
+
+    // Only a universe fails, when it cannot grow, see `Universe::step`
+    pub fn step(&mut self, generations: u32) -> Result<(), SimulationError> {
+        match self {
+            Space::World(world) => {
+                world.step(generations);
+                Ok(())
+            },
+            Space::Universe(universe) => universe.step(generations),
+        }
+    }
+
+    pub fn set_rule(&mut self, rule: Rule) -> Result<(), SimulationError> {
+        match self {
+            Space::World(world) => {
+                world.set_rule(rule);
+                Ok(())
+            },
+            Space::Universe(universe) => universe.set_rule(rule),
+        }
+    }
+
+    pub fn set_boundary(&mut self, boundary: Boundary) -> Result<(), SimulationError> {
+        match self {
+            Space::World(world) => world.set_boundary(boundary),
+            Space::Universe(_) => Err(SimulationError::UnboundedUniverse),
+        }
+    }
+
+    pub fn resize(&mut self, size: (u32, u32), anchor: Anchor) -> Result<(), SimulationError> {
+        match self {
+            Space::World(world) => world.resize(size, anchor),
+            Space::Universe(_) => Err(SimulationError::UnboundedUniverse),
+        }
+    }
+
+    // Sets a cell, wrapping the position around a torus
+    pub fn set_cell(&mut self, (x, y): (i64, i64), cell: Cell) -> Result<(), SimulationError> {
+        match self {
+            Space::World(world) => {
+                let (width, height) = world.size();
+                world.set_cell((x.rem_euclid(width as i64) as u32, y.rem_euclid(height as i64) as u32), cell);
+                Ok(())
+            },
+            Space::Universe(universe) => universe.set_cell((x, y), cell),
+        }
+    }
+
+    pub fn randomize(&mut self, generator: Generator, seed: u64, density: f64) -> Result<(), SimulationError> {
+        match self {
+            Space::World(world) => {
+                world.randomize(generator, seed, density);
+                Ok(())
+            },
+            Space::Universe(universe) => universe.randomize(generator, seed, density),
+        }
+    }
+
+    pub fn center_pattern(&mut self, pattern: &Pattern) -> Result<(), SimulationError> {
+        match self {
+            Space::World(world) => {
+                world.center_pattern(pattern);
+                Ok(())
+            },
+            Space::Universe(universe) => universe.center_pattern(pattern),
+        }
+    }
+
+    // Reads back the whole torus, or the smallest rectangle holding the live cells of a
+    // universe, with the position of its lowest corner
+    pub fn read_cells(&mut self) -> Result<((i64, i64), Grid), wgpu::BufferAsyncError> {
+        match self {
+            Space::World(world) => Ok(((0, 0), world.read_cells()?)),
+            Space::Universe(universe) => {
+                let (origin, size) = universe.live_bounds()?.unwrap_or(((0, 0), (0, 0)));
+                Ok((origin, universe.read_region(origin, size)?))
+            },
+        }
+    }
+
+    // Reads back the cells as a pattern tagged with the rule being simulated
+    pub fn export_pattern(&mut self) -> Result<Pattern, wgpu::BufferAsyncError> {
+        match self {
+            Space::World(world) => world.export_pattern(None),
+            Space::Universe(universe) => Ok(universe.export_pattern()?.1),
+        }
+    }
+}
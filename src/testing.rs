@@ -0,0 +1,63 @@
+// Fixtures shared by the tests. The tests that need a GPU adapter are ignored by default
+// and run with `cargo test -- --ignored`, where a missing adapter fails them. The tests of
+// a cell format the adapter cannot store pass without running.
+
+use crate::format::CellFormat;
+use crate::grid::{Cell, Grid};
+use crate::rule::Rule;
+use crate::simulation::{Simulation, SimulationError, SimulationOptions};
+use crate::universe::Universe;
+use crate::world::World;
+
+pub(crate) const GLIDER: &[(u32, u32)] = &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)];
+pub(crate) const R_PENTOMINO: &[(u32, u32)] = &[(1, 0), (2, 0), (0, 1), (1, 1), (1, 2)];
+
+// A dead grid with `cells` alive, offset by `(ox, oy)` and wrapped around its edges
+pub(crate) fn grid_with(width: u32, height: u32, cells: &[(u32, u32)], (ox, oy): (u32, u32)) -> Grid {
+    let mut grid = Grid::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            grid.set(x, y, Cell::DEAD);
+        }
+    }
+    for &(x, y) in cells {
+        grid.set((x + ox) % width, (y + oy) % height, Cell::ALIVE);
+    }
+    grid
+}
+
+pub(crate) fn alive_cells(grid: &Grid) -> Vec<(u32, u32)> {
+    (0..grid.height())
+        .flat_map(|y| (0..grid.width()).map(move |x| (x, y)))
+        .filter(|&(x, y)| grid.get(x, y).is_alive())
+        .collect()
+}
+
+pub(crate) fn gpu_simulation(size: (u32, u32), rule: Rule, format: CellFormat) -> Simulation {
+    gpu_simulation_with(SimulationOptions { size, rule, format, ..SimulationOptions::default() })
+}
+
+pub(crate) fn gpu_simulation_with(options: SimulationOptions) -> Simulation {
+    pollster::block_on(Simulation::new(&options)).unwrap_or_else(|err| panic!("{}", err))
+}
+
+// Like `gpu_simulation_with`, or None when the adapter cannot store cells in the format
+pub(crate) fn gpu_simulation_if_supported(options: SimulationOptions) -> Option<Simulation> {
+    match pollster::block_on(Simulation::new(&options)) {
+        Ok(simulation) => Some(simulation),
+        Err(SimulationError::UnsupportedFormat { .. }) => None,
+        Err(err) => panic!("{}", err),
+    }
+}
+
+// A world of r32uint chunks of at most `chunk_size` texels
+pub(crate) fn gpu_world(size: (u32, u32), chunk_size: u32) -> World {
+    let options = SimulationOptions { size, format: CellFormat::Uint32, ..SimulationOptions::default() };
+    pollster::block_on(World::new(&options, Some(chunk_size))).unwrap_or_else(|err| panic!("{}", err))
+}
+
+// An empty universe of r32uint chunks with `chunk_size` texels along each side
+pub(crate) fn gpu_universe(rule: Rule, chunk_size: u32) -> Universe {
+    let options = SimulationOptions { size: (64, 64), rule, format: CellFormat::Uint32, ..SimulationOptions::default() };
+    pollster::block_on(Universe::new(&options, Some(chunk_size))).unwrap_or_else(|err| panic!("{}", err))
+}
@@ -1,53 +1,243 @@
-@group(0) @binding(0) var input_texture : texture_2d<f32>;
-@group(0) @binding(1) var output_texture : texture_storage_2d<rg32float, write>;
-var<workgroup> neighbors: array<array<vec4<f32>, 16>, 16>;
+struct Rule {
+    birth: u32,
+    survival: u32,
+};
+
+// Edge of the square workgroups, filled in by `Simulation`. The cells around the edges
+// only load the halo, so each workgroup advances a tile of TILE_SIZE x TILE_SIZE cells.
+const WORKGROUP_SIZE: u32 = {workgroup_size}u;
+const TILE_SIZE: u32 = WORKGROUP_SIZE - 2u;
+
+// How the edges are joined, see boundary.rs
+struct Boundary {
+    mode: u32,
+};
+
+@group(1) @binding(0) var<uniform> rule: Rule;
+@group(1) @binding(1) var<uniform> boundary: Boundary;
+var<workgroup> neighbors: array<array<vec4<f32>, WORKGROUP_SIZE>, WORKGROUP_SIZE>;
+
+// Used by main_active only, see activity.rs
+@group(2) @binding(0) var<storage, read_write> changed: array<atomic<u32>>;
+@group(2) @binding(1) var<storage, read> active_tiles: array<u32>;
+
+// Marks padding at the end of active_tiles
+const NO_TILE: u32 = 0xffffffffu;
+// Workgroups per row of the indirect dispatch, matching activity.wgsl
+const ACTIVE_ROW: u32 = 256u;
+// Dead cells keep their tile active while the trail is still visible
+const TRAIL_VISIBLE: f32 = 0.01;
+// Boundary modes, matching `Boundary::uniform`
+const TORUS: u32 = 0u;
+const DEAD: u32 = 1u;
+const MIRROR: u32 = 2u;
+const KLEIN: u32 = 3u;
+const CROSS: u32 = 4u;
+// Cells beyond a dead edge
+const DEAD_CELL: vec4<f32> = vec4<f32>(-1.0, 0.0, 0.0, 0.0);
+// Generations advanced by main_blocked, less than WORKGROUP_SIZE / 2
+override BLOCK_GENERATIONS: u32 = 1u;
 
 @compute
-@workgroup_size(16, 16)
+@workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE)
 fn main(
     @builtin(local_invocation_id) local_id: vec3<u32>,
-    @builtin(global_invocation_id) global_id: vec3<u32>,
     @builtin(workgroup_id) workgroup_id: vec3<u32>,
 ) {
-    // Compute global texture coords
-    var coords: vec2<u32> = workgroup_id.xy * vec2<u32>(14, 14) + local_id.xy - vec2<u32>(1, 1);
-    coords.x = coords.x % textureDimensions(input_texture).x;
-    coords.y = coords.y % textureDimensions(input_texture).y;
+    transition_cell(cell_coords(workgroup_id.xy, local_id.xy), local_id.xy);
+}
+
+// Only transitions the tiles listed in active_tiles and flags the tiles owning cells
+// that changed
+@compute
+@workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE)
+fn main_active(
+    @builtin(local_invocation_id) local_id: vec3<u32>,
+    @builtin(workgroup_id) workgroup_id: vec3<u32>,
+) {
+    let tile = active_tiles[workgroup_id.y * ACTIVE_ROW + workgroup_id.x];
+    if tile == NO_TILE {
+        return;
+    }
+
+    let columns = (textureDimensions(input_texture).x + TILE_SIZE - 1u) / TILE_SIZE;
+    let coords = cell_coords(vec2<u32>(tile % columns, tile / columns), local_id.xy);
+    if transition_cell(coords, local_id.xy) {
+        flag_tile(coords, columns);
+
+        // activity.wgsl only activates the tiles next to a flagged tile on the torus, so
+        // flag the tiles of the neighbors across the other edges
+        if boundary.mode != TORUS {
+            for (var dy = -1; dy <= 1; dy++) {
+                for (var dx = -1; dx <= 1; dx++) {
+                    let neighbor = coords + vec2<i32>(dx, dy);
+                    if is_outside(neighbor) && boundary.mode != DEAD {
+                        flag_tile(locate(neighbor), columns);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn flag_tile(coords: vec2<i32>, columns: u32) {
+    let tile = vec2<u32>(coords) / TILE_SIZE;
+    atomicOr(&changed[tile.y * columns + tile.x], 1u);
+}
+
+// Advances BLOCK_GENERATIONS generations in workgroup memory. The halo is
+// BLOCK_GENERATIONS cells wide, so workgroups are WORKGROUP_SIZE - 2 * BLOCK_GENERATIONS
+// cells apart.
+@compute
+@workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE)
+fn main_blocked(
+    @builtin(local_invocation_id) local_id: vec3<u32>,
+    @builtin(workgroup_id) workgroup_id: vec3<u32>,
+) {
+    let stride = WORKGROUP_SIZE - 2u * BLOCK_GENERATIONS;
+    let coords = vec2<i32>(workgroup_id.xy * stride + local_id.xy) - i32(BLOCK_GENERATIONS);
+
+    // The cells beyond the other edges are images of cells in the grid and evolve like
+    // them, but cells beyond dead edges stay dead
+    let frozen = boundary.mode == DEAD && is_outside(coords);
+    neighbors[local_id.x][local_id.y] = load_neighbor(coords);
+    for (var generation = 0u; generation < BLOCK_GENERATIONS; generation++) {
+        workgroupBarrier();
+
+        // The edges keep their stale value, invalidating one more ring of cells each generation
+        var cell = neighbors[local_id.x][local_id.y];
+        if all(local_id.xy > vec2<u32>(0u)) && all(local_id.xy < vec2<u32>(WORKGROUP_SIZE - 1u)) && !frozen {
+            cell = next_cell(cell, count_neighbors(local_id.xy));
+        }
+        workgroupBarrier();
+        neighbors[local_id.x][local_id.y] = cell;
+    }
 
+    let in_block = all(local_id.xy >= vec2<u32>(BLOCK_GENERATIONS)) && all(local_id.xy < vec2<u32>(WORKGROUP_SIZE - BLOCK_GENERATIONS));
+    if in_block && !is_outside(coords) {
+        store_cell(vec2<u32>(coords), neighbors[local_id.x][local_id.y]);
+    }
+}
+
+// Position of the cell loaded by a thread, which is beyond the edges for the halo and
+// for tiles overhanging the grid
+fn cell_coords(tile: vec2<u32>, local_id: vec2<u32>) -> vec2<i32> {
+    return vec2<i32>(tile * TILE_SIZE + local_id) - 1;
+}
+
+fn is_outside(coords: vec2<i32>) -> bool {
+    return any(coords < vec2<i32>(0)) || any(coords >= vec2<i32>(textureDimensions(input_texture)));
+}
+
+// Mirrors `Boundary::locate`, returning the cell of the grid found at a position beyond
+// the edges. Positions beyond dead edges are returned unchanged.
+fn locate(coords: vec2<i32>) -> vec2<i32> {
+    let dimensions = vec2<i32>(textureDimensions(input_texture));
+    switch boundary.mode {
+        case DEAD: {
+            return coords;
+        }
+        case MIRROR: {
+            let position = vec2<i32>(wrap(coords.x, 2 * dimensions.x), wrap(coords.y, 2 * dimensions.y));
+            return select(position, 2 * dimensions - 1 - position, position >= dimensions);
+        }
+        case KLEIN: {
+            let x = wrap(coords.x, dimensions.x);
+            let twisted = (floor_div(coords.y, dimensions.y) & 1) != 0;
+            return vec2<i32>(select(x, dimensions.x - 1 - x, twisted), wrap(coords.y, dimensions.y));
+        }
+        case CROSS: {
+            // Crossing the left or right edge flips y, then crossing the top or bottom
+            // edge flips x
+            var y = coords.y;
+            if (floor_div(coords.x, dimensions.x) & 1) != 0 {
+                y = dimensions.y - 1 - y;
+            }
+            var x = wrap(coords.x, dimensions.x);
+            if (floor_div(y, dimensions.y) & 1) != 0 {
+                x = dimensions.x - 1 - x;
+            }
+            return vec2<i32>(x, wrap(y, dimensions.y));
+        }
+        default: {
+            return vec2<i32>(wrap(coords.x, dimensions.x), wrap(coords.y, dimensions.y));
+        }
+    }
+}
+
+fn wrap(position: i32, size: i32) -> i32 {
+    return position - floor_div(position, size) * size;
+}
+
+// Division rounding towards negative infinity
+fn floor_div(a: i32, b: i32) -> i32 {
+    return select(a / b, (a + 1) / b - 1, a < 0);
+}
+
+fn load_neighbor(coords: vec2<i32>) -> vec4<f32> {
+    if is_outside(coords) {
+        if boundary.mode == DEAD {
+            return DEAD_CELL;
+        }
+        return load_cell(vec2<u32>(locate(coords)));
+    }
+    return load_cell(vec2<u32>(coords));
+}
+
+// Writes the next state of the cell, returning whether the cell changed
+fn transition_cell(coords: vec2<i32>, local_id: vec2<u32>) -> bool {
     // Read the neighbors into shared memory
-    neighbors[local_id.x][local_id.y] = textureLoad(input_texture, coords, 0);
+    neighbors[local_id.x][local_id.y] = load_neighbor(coords);
     workgroupBarrier(); // wait for all threads in the workgroup to finish
 
-    // Discard the threads around the edges
-    if local_id.x == 0 | local_id.x == 15 | local_id.y == 0 | local_id.y == 15 {
-        return;
+    // Discard the threads around the edges, and the cells of overhanging tiles that are
+    // beyond the grid
+    if local_id.x == 0 | local_id.x == WORKGROUP_SIZE - 1u | local_id.y == 0 | local_id.y == WORKGROUP_SIZE - 1u {
+        return false;
+    }
+    if is_outside(coords) {
+        return false;
     }
 
-    // Sum neighbors
-    var color: vec4<f32> = neighbors[local_id.x][local_id.y];
+    let previous: vec4<f32> = neighbors[local_id.x][local_id.y];
+    let color = next_cell(previous, count_neighbors(local_id));
+    store_cell(vec2<u32>(coords), color);
+    return (color.r > 0.0) != (previous.r > 0.0) || (color.r < 0.0 && color.g > TRAIL_VISIBLE);
+}
+
+// Sums the live neighbors of a cell in workgroup memory
+fn count_neighbors(local_id: vec2<u32>) -> u32 {
     var count: u32 = 0;
-    count += count_neighbor(local_id.xy, vec2<i32>( 1, 1));
-    count += count_neighbor(local_id.xy, vec2<i32>( 0, 1));
-    count += count_neighbor(local_id.xy, vec2<i32>(-1, 1));
-    count += count_neighbor(local_id.xy, vec2<i32>( 1, 0));
-    count += count_neighbor(local_id.xy, vec2<i32>(-1, 0));
-    count += count_neighbor(local_id.xy, vec2<i32>( 1,-1));
-    count += count_neighbor(local_id.xy, vec2<i32>( 0,-1));
-    count += count_neighbor(local_id.xy, vec2<i32>(-1,-1));
-
-    // Apply Game of Life rules
-    if (count == 3) {
+    count += count_neighbor(local_id, vec2<i32>( 1, 1));
+    count += count_neighbor(local_id, vec2<i32>( 0, 1));
+    count += count_neighbor(local_id, vec2<i32>(-1, 1));
+    count += count_neighbor(local_id, vec2<i32>( 1, 0));
+    count += count_neighbor(local_id, vec2<i32>(-1, 0));
+    count += count_neighbor(local_id, vec2<i32>( 1,-1));
+    count += count_neighbor(local_id, vec2<i32>( 0,-1));
+    count += count_neighbor(local_id, vec2<i32>(-1,-1));
+    return count;
+}
+
+fn next_cell(cell: vec4<f32>, count: u32) -> vec4<f32> {
+    var color = cell;
+
+    // Apply the birth and survival rules
+    if (color.r > 0.0) {
+        if ((rule.survival >> count) & 1u) == 0u {
+            color.r = -1.0;
+        }
+    } else if ((rule.birth >> count) & 1u) != 0u {
         color.r = 1.0;
-    } else if (count != 2) {
+    } else {
         color.r = -1.0;
     }
 
     // Set green channel for display
     color.g = max(color.r, color.g * 0.99);
-
-    textureStore(output_texture, coords, color);
+    return color;
 }
 
 fn count_neighbor(coords: vec2<u32>, offset: vec2<i32>) -> u32 {
     return u32(neighbors[i32(coords.x) + offset.x][i32(coords.y) + offset.y].r > 0.0);
-}
\ No newline at end of file
+}
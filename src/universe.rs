@@ -0,0 +1,558 @@
+//! An unbounded plane of cells made of chunks allocated on demand.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use crate::boundary::Boundary;
+use crate::bounds::{self, CORNERS_SIZE};
+use crate::format::CellFormat;
+use crate::generator::Generator;
+use crate::grid::{Cell, Grid};
+use crate::pattern::Pattern;
+use crate::rule::Rule;
+use crate::simulation::{Gpu, Kernel, Pipelines, Simulation, SimulationError, SimulationOptions};
+use crate::world::{copy_halo, stage_edges, HALO, NEIGHBORS};
+
+// Side of the chunk textures unless another one is asked for
+const DEFAULT_CHUNK_SIZE: u32 = 256;
+
+// Most generations advanced between checks for live cells nearing missing chunks
+const MAX_BATCH: u32 = 64;
+
+// The column and row of a chunk, chunk (0, 0) holding the cells from (0, 0) on
+type ChunkKey = (i64, i64);
+
+// The lowest and highest corner of a rectangle of cells, both included
+type Rect = ((i64, i64), (i64, i64));
+
+// The lowest corner and the size of a rectangle of cells
+type Region = ((i64, i64), (u32, u32));
+
+struct Chunk {
+    simulation: Simulation,
+    // A rectangle holding every live cell of the chunk, or None when it has none. Only
+    // exact after `find_live_cells`, it grows with the generations in between.
+    bounds: Option<Rect>,
+}
+
+/// An unbounded plane of cells. Only the chunks holding live cells and the ones live
+/// cells can reach are allocated, each simulated by a `Simulation` on a shared device
+/// with a one cell halo like the chunks of a `World`. The halos next to missing chunks
+/// stay dead.
+///
+/// Live cells spread by at most one cell per generation, so the generations are run in
+/// batches after allocating every chunk the live cells can reach during the batch. The
+/// rectangles holding the live cells are only read back from the GPU when they may
+/// have reached a missing chunk, which is also when the empty chunks out of reach are
+/// freed. Rules giving birth to cells without live neighbors cannot be simulated.
+pub struct Universe {
+    gpu: Gpu,
+    // Shared by the chunks
+    pipelines: Arc<Pipelines>,
+    options: SimulationOptions,
+    // Cells along each side of the chunk interiors
+    chunk_size: u32,
+    chunks: BTreeMap<ChunkKey, Chunk>,
+    // A chunk that stays dead, copied into the halos next to missing chunks
+    empty: Simulation,
+    generation: u64,
+    revision: u64,
+}
+
+impl Universe {
+    /// Creates an empty universe on a new instance, see `with_instance`
+    pub async fn new(options: &SimulationOptions, chunk_size: Option<u32>) -> Result<Universe, SimulationError> {
+        let instance = wgpu::Instance::default();
+        Universe::with_instance(&instance, None, options, chunk_size).await
+    }
+
+    /// Creates an empty universe on an adapter of `instance` able to present to
+    /// `compatible_surface`, if one is given. `chunk_size` is the side of the chunk
+    /// textures. `options.size` is the region `randomize` fills and `center_pattern`
+    /// centers patterns in, with its lowest corner at the origin.
+    pub async fn with_instance(
+        instance: &wgpu::Instance,
+        compatible_surface: Option<&wgpu::Surface<'_>>,
+        options: &SimulationOptions,
+        chunk_size: Option<u32>,
+    ) -> Result<Universe, SimulationError> {
+        if options.kernel != Kernel::Texture
+            || options.skip_stable_tiles
+            || options.block_generations > 1
+            || options.boundary != Boundary::Torus
+        {
+            return Err(SimulationError::UnboundedUniverse);
+        }
+        if options.rule.is_born(0) {
+            return Err(SimulationError::BirthWithoutNeighbors(options.rule));
+        }
+
+        let gpu = Gpu::request(instance, compatible_surface, options).await?;
+        let max = gpu.device.limits().max_texture_dimension_2d;
+        let texture_size = chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE).min(max);
+        if texture_size <= 2 * HALO {
+            return Err(SimulationError::InvalidSize { size: (texture_size, texture_size), max });
+        }
+
+        let options = options.clone();
+        let chunk_size = texture_size - 2 * HALO;
+        let pipelines = Arc::new(Pipelines::new(&gpu.device, &options));
+        let empty = Simulation::with_pipelines(gpu.clone(), Arc::clone(&pipelines), &Universe::chunk_options(&options, chunk_size))?;
+        Ok(Universe {
+            gpu,
+            pipelines,
+            options,
+            chunk_size,
+            chunks: BTreeMap::new(),
+            empty,
+            generation: 0,
+            revision: 0,
+        })
+    }
+
+    fn chunk_options(options: &SimulationOptions, chunk_size: u32) -> SimulationOptions {
+        SimulationOptions {
+            size: (chunk_size + 2 * HALO, chunk_size + 2 * HALO),
+            ..options.clone()
+        }
+    }
+
+    /// The adapter the shared device was created on
+    pub fn adapter(&self) -> &wgpu::Adapter {
+        self.empty.adapter()
+    }
+
+    /// The device shared by the chunks
+    pub fn device(&self) -> &wgpu::Device {
+        self.empty.device()
+    }
+
+    /// The queue of the shared device
+    pub fn queue(&self) -> &wgpu::Queue {
+        self.empty.queue()
+    }
+
+    /// How the cells are stored in the chunk textures
+    pub fn format(&self) -> CellFormat {
+        self.options.format
+    }
+
+    /// Generations advanced since the universe was created or reset
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// The rule used by the next generation
+    pub fn rule(&self) -> Rule {
+        self.options.rule
+    }
+
+    /// Cells along each side of the chunk interiors
+    pub fn chunk_size(&self) -> u32 {
+        self.chunk_size
+    }
+
+    /// Cells around the interior of each chunk texture
+    pub fn halo(&self) -> u32 {
+        HALO
+    }
+
+    /// The allocated chunks with the position and size of their interiors
+    pub fn chunks(&self) -> impl Iterator<Item = ((i64, i64), (u32, u32), &Simulation)> + '_ {
+        self.chunks.iter().map(|(&key, chunk)| {
+            (chunk_rect(key, self.chunk_size).0, (self.chunk_size, self.chunk_size), &chunk.simulation)
+        })
+    }
+
+    /// Changes whenever chunks are allocated or freed
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Switches to another rule, which must not give birth to cells without live neighbors
+    pub fn set_rule(&mut self, rule: Rule) -> Result<(), SimulationError> {
+        if rule.is_born(0) {
+            return Err(SimulationError::BirthWithoutNeighbors(rule));
+        }
+        self.options.rule = rule;
+        self.empty.set_rule(rule);
+        for chunk in self.chunks.values_mut() {
+            chunk.simulation.set_rule(rule);
+        }
+        Ok(())
+    }
+
+    /// Whether the device was lost and `recover` must be called before stepping
+    pub fn is_device_lost(&self) -> bool {
+        self.empty.is_device_lost()
+    }
+
+    /// Takes the first wgpu error raised since the last call, see `Simulation::take_error`
+    pub fn take_error(&self) -> Option<wgpu::Error> {
+        self.empty.take_error()
+    }
+
+    /// Starts reading back every chunk for `recover`, see `Simulation::take_snapshot`
+    pub fn take_snapshot(&mut self) {
+        for chunk in self.chunks.values_mut() {
+            chunk.simulation.take_snapshot();
+        }
+    }
+
+    /// Keeps the snapshots whose copies are done, see `Simulation::poll_snapshot`
+    pub fn poll_snapshot(&mut self) -> bool {
+        self.chunks.values_mut().fold(false, |pending, chunk| chunk.simulation.poll_snapshot() | pending)
+    }
+
+    /// Recreates the device and the chunks after the device was lost, see
+    /// `Simulation::recover`. Chunks allocated after the last snapshot come back dead.
+    pub async fn recover(
+        &mut self,
+        instance: &wgpu::Instance,
+        compatible_surface: Option<&wgpu::Surface<'_>>,
+    ) -> Result<(), SimulationError> {
+        let gpu = Gpu::request(instance, compatible_surface, &self.options).await?;
+        let pipelines = Arc::new(Pipelines::new(&gpu.device, &self.options));
+        self.empty.recover_on(gpu.clone(), Arc::clone(&pipelines))?;
+        for (&key, chunk) in &mut self.chunks {
+            chunk.simulation.recover_on(gpu.clone(), Arc::clone(&pipelines))?;
+            // The snapshot may hold cells outside the rectangle grown since
+            chunk.bounds = Some(chunk_rect(key, self.chunk_size));
+        }
+        self.gpu = gpu;
+        self.pipelines = pipelines;
+        self.revision += 1;
+        Ok(())
+    }
+
+    /// Advances the universe by the given number of generations, allocating and freeing
+    /// chunks between batches. Fails when a chunk cannot be allocated or the live cells
+    /// cannot be read back, after the batches that could be run.
+    pub fn step(&mut self, generations: u32) -> Result<(), SimulationError> {
+        let mut remaining = generations;
+        while remaining > 0 {
+            // Live cells must not spread beyond the neighbors of their chunk in one batch,
+            // and spreading at most half a chunk keeps small patterns within a few chunks
+            let batch = remaining.min(MAX_BATCH).min(self.chunk_size.div_ceil(2));
+            self.prepare_chunks(batch)?;
+
+            let mut encoder = self.device().create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("universe_transition_encoder"),
+            });
+            for _ in 0..batch {
+                self.encode_halo_exchange(&mut encoder);
+                for chunk in self.chunks.values_mut() {
+                    chunk.simulation.transition(&mut encoder, 1);
+                }
+            }
+            self.queue().submit([encoder.finish()]);
+
+            // Grow the rectangles holding live cells by the batch, into the neighbors
+            let grown: Vec<Rect> = self.chunks.values().filter_map(|chunk| chunk.bounds).map(|bounds| grow(bounds, batch)).collect();
+            for (&key, chunk) in &mut self.chunks {
+                let rect = chunk_rect(key, self.chunk_size);
+                chunk.bounds = grown.iter().filter_map(|&bounds| intersect(bounds, rect)).reduce(union);
+            }
+
+            self.generation += batch as u64;
+            remaining -= batch;
+        }
+        Ok(())
+    }
+
+    // Allocates every chunk live cells can reach in the given number of generations and
+    // frees the empty chunks out of reach. The live cells are only looked for when their
+    // grown rectangles reach a missing chunk.
+    fn prepare_chunks(&mut self, generations: u32) -> Result<(), SimulationError> {
+        let mut reached = self.reached_chunks(generations);
+        if reached.iter().any(|key| !self.chunks.contains_key(key)) {
+            self.find_live_cells().map_err(SimulationError::ReadBack)?;
+            reached = self.reached_chunks(generations);
+        }
+
+        let count = self.chunks.len();
+        self.chunks.retain(|key, chunk| chunk.bounds.is_some() || reached.contains(key));
+        if self.chunks.len() != count {
+            self.revision += 1;
+        }
+        for key in reached {
+            self.chunk_mut(key)?;
+        }
+        Ok(())
+    }
+
+    // The chunks live cells can reach in the given number of generations
+    fn reached_chunks(&self, generations: u32) -> BTreeSet<ChunkKey> {
+        let mut reached = BTreeSet::new();
+        for bounds in self.chunks.values().filter_map(|chunk| chunk.bounds) {
+            let ((min_x, min_y), (max_x, max_y)) = grow(bounds, generations);
+            let size = self.chunk_size as i64;
+            for y in min_y.div_euclid(size)..=max_y.div_euclid(size) {
+                for x in min_x.div_euclid(size)..=max_x.div_euclid(size) {
+                    reached.insert((x, y));
+                }
+            }
+        }
+        reached
+    }
+
+    // Replaces the grown rectangles of every chunk with the exact ones, found on the GPU
+    // and read back together
+    fn find_live_cells(&mut self) -> Result<(), wgpu::BufferAsyncError> {
+        if self.chunks.is_empty() {
+            return Ok(());
+        }
+        let staging_buffer = self.device().create_buffer(&wgpu::BufferDescriptor {
+            label: Some("bounds_readback_buffer"),
+            size: CORNERS_SIZE * self.chunks.len() as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let mut encoder = self.device().create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("bounds_encoder"),
+        });
+        for (index, chunk) in self.chunks.values_mut().enumerate() {
+            chunk.simulation.encode_live_bounds(&mut encoder, HALO, &staging_buffer, CORNERS_SIZE * index as u64);
+        }
+        let submission_index = self.queue().submit([encoder.finish()]);
+
+        // Map the staging buffer and wait for the searches to finish
+        let buffer_slice = staging_buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        buffer_slice.map_async(wgpu::MapMode::Read, move |result| sender.send(result).unwrap());
+        self.device().poll(wgpu::Maintain::WaitForSubmissionIndex(submission_index));
+        receiver.recv().unwrap_or(Err(wgpu::BufferAsyncError))?;
+        {
+            let data = buffer_slice.get_mapped_range();
+            let words: &[u32] = bytemuck::cast_slice(&data);
+            for ((&key, chunk), corners) in self.chunks.iter_mut().zip(words.chunks_exact(4)) {
+                // The texels of the interior start after the halo
+                let ((origin_x, origin_y), _) = chunk_rect(key, self.chunk_size);
+                let texel = |(x, y): (u32, u32)| (origin_x + (x - HALO) as i64, origin_y + (y - HALO) as i64);
+                chunk.bounds = bounds::decode(corners).map(|(min, max)| (texel(min), texel(max)));
+            }
+        }
+        staging_buffer.unmap();
+        Ok(())
+    }
+
+    // Copies the cells along the edges of every chunk into the halos of its neighbors,
+    // and dead cells into the halos next to missing chunks
+    fn encode_halo_exchange(&self, encoder: &mut wgpu::CommandEncoder) {
+        let size = (self.chunk_size, self.chunk_size);
+        for chunk in self.chunks.values() {
+            stage_edges(encoder, &chunk.simulation, size);
+        }
+        for (&(x, y), chunk) in &self.chunks {
+            for offset @ (dx, dy) in NEIGHBORS {
+                let neighbor = self.chunks.get(&(x + dx, y + dy)).map_or(&self.empty, |neighbor| &neighbor.simulation);
+                copy_halo(encoder, (&chunk.simulation, size), (neighbor.write_texture(), size), offset);
+            }
+        }
+    }
+
+    // The chunk, allocated dead if it is missing. The options were checked when the
+    // universe was created, so only running out of memory fails.
+    fn chunk_mut(&mut self, key: ChunkKey) -> Result<&mut Chunk, SimulationError> {
+        if !self.chunks.contains_key(&key) {
+            let options = Universe::chunk_options(&self.options, self.chunk_size);
+            self.device().push_error_scope(wgpu::ErrorFilter::OutOfMemory);
+            let simulation = Simulation::with_pipelines(self.gpu.clone(), Arc::clone(&self.pipelines), &options);
+            if let Some(err) = pollster::block_on(self.device().pop_error_scope()) {
+                return Err(SimulationError::OutOfMemory(err));
+            }
+            self.chunks.insert(key, Chunk { simulation: simulation?, bounds: None });
+            self.revision += 1;
+        }
+        Ok(self.chunks.get_mut(&key).unwrap())
+    }
+
+    /// Overwrites the cells of a region, allocating the chunks it covers. Fails when a
+    /// chunk cannot be allocated, after writing the chunks that could.
+    pub fn set_region(&mut self, (x, y): (i64, i64), grid: &Grid) -> Result<(), SimulationError> {
+        if grid.width() == 0 || grid.height() == 0 {
+            return Ok(());
+        }
+        let region = ((x, y), (x + grid.width() as i64 - 1, y + grid.height() as i64 - 1));
+        let size = self.chunk_size as i64;
+        for row in y.div_euclid(size)..=region.1 .1.div_euclid(size) {
+            for column in x.div_euclid(size)..=region.1 .0.div_euclid(size) {
+                let key = (column, row);
+                let rect = chunk_rect(key, self.chunk_size);
+                let Some(overlap @ ((min_x, min_y), (max_x, max_y))) = intersect(region, rect) else { continue };
+                let part = grid.crop(
+                    ((min_x - x) as u32, (min_y - y) as u32),
+                    ((max_x - min_x + 1) as u32, (max_y - min_y + 1) as u32),
+                );
+                let origin = ((min_x - rect.0 .0) as u32 + HALO, (min_y - rect.0 .1) as u32 + HALO);
+                let chunk = self.chunk_mut(key)?;
+                chunk.simulation.set_region(origin, &part);
+                chunk.bounds = Some(chunk.bounds.map_or(overlap, |bounds| union(bounds, overlap)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Overwrites a single cell, allocating its chunk
+    pub fn set_cell(&mut self, position: (i64, i64), cell: Cell) -> Result<(), SimulationError> {
+        self.set_region(position, &Grid::from_cells(1, 1, vec![cell]))
+    }
+
+    /// Overwrites the region of the pattern with its top left corner at `origin`
+    pub fn place_pattern(&mut self, origin: (i64, i64), pattern: &Pattern) -> Result<(), SimulationError> {
+        self.set_region(origin, &pattern.to_grid())
+    }
+
+    /// Places the pattern in the middle of the region of `options.size` at the origin
+    pub fn center_pattern(&mut self, pattern: &Pattern) -> Result<(), SimulationError> {
+        let (width, height) = self.options.size;
+        let origin = (
+            (width as i64 - pattern.width() as i64) / 2,
+            (height as i64 - pattern.height() as i64) / 2,
+        );
+        self.place_pattern(origin, pattern)
+    }
+
+    /// Kills every cell, freeing all chunks, and starts over at generation zero
+    pub fn reset(&mut self) {
+        self.chunks.clear();
+        self.generation = 0;
+        self.revision += 1;
+    }
+
+    /// Starts over with the region of `options.size` at the origin filled by the
+    /// generator, see `Simulation::randomize`
+    pub fn randomize(&mut self, generator: Generator, seed: u64, density: f64) -> Result<(), SimulationError> {
+        self.reset();
+        self.set_region((0, 0), &generator.generate(self.options.size, seed, density))
+    }
+
+    /// Copies the cells of a region back to the CPU, the cells of missing chunks being
+    /// dead, see `Simulation::read_cells`
+    pub fn read_region(&self, (x, y): (i64, i64), (width, height): (u32, u32)) -> Result<Grid, wgpu::BufferAsyncError> {
+        let mut grid = Grid::new(width, height);
+        if width == 0 || height == 0 {
+            return Ok(grid);
+        }
+        let region = ((x, y), (x + width as i64 - 1, y + height as i64 - 1));
+        for (&key, chunk) in &self.chunks {
+            let rect = chunk_rect(key, self.chunk_size);
+            let Some(((min_x, min_y), (max_x, max_y))) = intersect(region, rect) else { continue };
+            let cells = chunk.simulation.read_cells_blocking()?;
+            for cell_y in min_y..=max_y {
+                for cell_x in min_x..=max_x {
+                    let cell = cells.get((cell_x - rect.0 .0) as u32 + HALO, (cell_y - rect.0 .1) as u32 + HALO);
+                    grid.set((cell_x - x) as u32, (cell_y - y) as u32, cell);
+                }
+            }
+        }
+        Ok(grid)
+    }
+
+    /// The lowest corner and the size of the smallest rectangle holding every live cell,
+    /// or None when there are none
+    pub fn live_bounds(&mut self) -> Result<Option<Region>, wgpu::BufferAsyncError> {
+        self.find_live_cells()?;
+        let bounds = self.chunks.values().filter_map(|chunk| chunk.bounds).reduce(union);
+        Ok(bounds.map(|((min_x, min_y), (max_x, max_y))| {
+            ((min_x, min_y), ((max_x - min_x + 1) as u32, (max_y - min_y + 1) as u32))
+        }))
+    }
+
+    /// Reads back the live cells as a pattern tagged with the rule being simulated,
+    /// together with the position of its lowest corner
+    pub fn export_pattern(&mut self) -> Result<((i64, i64), Pattern), wgpu::BufferAsyncError> {
+        let (origin, mut pattern) = match self.live_bounds()? {
+            Some((origin, size)) => {
+                let grid = self.read_region(origin, size)?;
+                (origin, Pattern::from_grid(&grid, (0, 0), size))
+            },
+            None => ((0, 0), Pattern::new(0, 0)),
+        };
+        pattern.set_rule(Some(self.rule()));
+        Ok((origin, pattern))
+    }
+}
+
+// The rectangle of cells in the interior of a chunk
+fn chunk_rect((x, y): ChunkKey, chunk_size: u32) -> Rect {
+    let size = chunk_size as i64;
+    ((x * size, y * size), ((x + 1) * size - 1, (y + 1) * size - 1))
+}
+
+fn grow(((min_x, min_y), (max_x, max_y)): Rect, cells: u32) -> Rect {
+    let cells = cells as i64;
+    ((min_x - cells, min_y - cells), (max_x + cells, max_y + cells))
+}
+
+fn intersect(((a_min_x, a_min_y), (a_max_x, a_max_y)): Rect, ((b_min_x, b_min_y), (b_max_x, b_max_y)): Rect) -> Option<Rect> {
+    let rect = ((a_min_x.max(b_min_x), a_min_y.max(b_min_y)), (a_max_x.min(b_max_x), a_max_y.min(b_max_y)));
+    (rect.0 .0 <= rect.1 .0 && rect.0 .1 <= rect.1 .1).then_some(rect)
+}
+
+fn union(((a_min_x, a_min_y), (a_max_x, a_max_y)): Rect, ((b_min_x, b_min_y), (b_max_x, b_max_y)): Rect) -> Rect {
+    ((a_min_x.min(b_min_x), a_min_y.min(b_min_y)), (a_max_x.max(b_max_x), a_max_y.max(b_max_y)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reference::{first_difference, step_n};
+    use crate::testing::{gpu_universe, grid_with, GLIDER, R_PENTOMINO};
+
+    #[test]
+    #[ignore = "needs a GPU adapter, run with --ignored"]
+    fn unbounded_universe_matches() {
+        // Cells cannot spread further than one cell per generation, so a dead boundary that
+        // far away matches the unbounded plane
+        let generations = 120;
+        for rule in [Rule::CONWAY, "B36/S23".parse().unwrap()] {
+            let mut universe = gpu_universe(rule, 34);
+            universe.set_region((-2, 30), &grid_with(3, 3, R_PENTOMINO, (0, 0))).unwrap();
+            universe.step(generations).unwrap();
+            assert_eq!(universe.generation(), generations as u64);
+
+            let padding = generations as i64;
+            let size = (3 + 2 * generations, 3 + 2 * generations);
+            let initial = grid_with(size.0, size.1, R_PENTOMINO, (generations, generations));
+            let expected = step_n(&initial, rule, Boundary::Dead, generations);
+            let actual = universe.read_region((-2 - padding, 30 - padding), size).unwrap();
+            assert_eq!(first_difference(&expected, &actual), None, "{}", rule);
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter, run with --ignored"]
+    fn universe_frees_chunks_left_behind() {
+        let mut universe = gpu_universe(Rule::CONWAY, 18);
+        universe.set_region((10, 10), &grid_with(3, 3, GLIDER, (0, 0))).unwrap();
+        for _ in 0..10 {
+            universe.step(40).unwrap();
+            // The glider only ever needs the chunks around it
+            assert!(universe.chunks().count() <= 9, "{} chunks", universe.chunks().count());
+        }
+
+        // The glider moves one cell diagonally every four generations
+        let (origin, pattern) = universe.export_pattern().unwrap();
+        assert_eq!(origin, (110, 110));
+        assert_eq!(pattern.to_grid(), grid_with(3, 3, GLIDER, (0, 0)));
+        assert_eq!(pattern.rule(), Some(Rule::CONWAY));
+
+        universe.reset();
+        assert_eq!(universe.live_bounds().unwrap(), None);
+        assert!(matches!(universe.set_rule("B0/S".parse().unwrap()), Err(SimulationError::BirthWithoutNeighbors(_))));
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter, run with --ignored"]
+    fn universe_step_reports_device_loss() {
+        let mut universe = gpu_universe(Rule::CONWAY, 18);
+        universe.set_region((10, 10), &grid_with(3, 3, GLIDER, (0, 0))).unwrap();
+        universe.step(8).unwrap();
+
+        // Growing towards missing chunks needs the live cells, which can no longer be read
+        universe.device().destroy();
+        universe.device().poll(wgpu::Maintain::Wait);
+        assert!(universe.is_device_lost());
+        assert!(matches!(universe.step(100), Err(SimulationError::ReadBack(_))));
+    }
+}
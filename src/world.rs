@@ -0,0 +1,512 @@
+//! A torus that may be split into several textures.
+
+use std::sync::Arc;
+use crate::boundary::Boundary;
+use crate::format::CellFormat;
+use crate::generator::Generator;
+use crate::grid::{Anchor, Cell, Grid};
+use crate::pattern::Pattern;
+use crate::rule::Rule;
+use crate::simulation::{wrapped_spans, Gpu, Kernel, Pipelines, Simulation, SimulationError, SimulationOptions};
+
+// Cells around the interior of each chunk that are copied from its neighbors
+pub(crate) const HALO: u32 = 1;
+
+/// A torus that may be larger than the largest texture of the adapter. The world is
+/// split into a grid of chunks, each simulated by a `Simulation` on a shared device
+/// whose textures hold the interior of the chunk and a one cell halo around it. Before
+/// every generation the cells along the edges of each chunk are copied into the halos
+/// of its neighbors, so the chunks advance as one continuous torus.
+///
+/// A world that fits in a single chunk has no halo and supports every option of a
+/// `Simulation`. A world of several chunks needs the texture kernel on a torus,
+/// advancing one generation per dispatch without skipping stable tiles.
+pub struct World {
+    size: (u32, u32),
+    // Interior widths of the chunk columns and heights of the chunk rows
+    columns: Vec<u32>,
+    rows: Vec<u32>,
+    // The chunks row by row
+    chunks: Vec<Simulation>,
+    halo: u32,
+    options: SimulationOptions,
+}
+
+impl World {
+    /// Creates a world on a new instance with a dead grid, see `with_instance`
+    pub async fn new(options: &SimulationOptions, chunk_size: Option<u32>) -> Result<World, SimulationError> {
+        let instance = wgpu::Instance::default();
+        World::with_instance(&instance, None, options, chunk_size).await
+    }
+
+    /// Creates a world of `options.size` on an adapter of `instance` able to present to
+    /// `compatible_surface`, if one is given. `chunk_size` caps the sides of the chunk
+    /// interiors and defaults to the largest texture of the adapter.
+    pub async fn with_instance(
+        instance: &wgpu::Instance,
+        compatible_surface: Option<&wgpu::Surface<'_>>,
+        options: &SimulationOptions,
+        chunk_size: Option<u32>,
+    ) -> Result<World, SimulationError> {
+        let gpu = Gpu::request(instance, compatible_surface, options).await?;
+        World::on_gpu(gpu, options, chunk_size)
+    }
+
+    fn on_gpu(gpu: Gpu, options: &SimulationOptions, chunk_size: Option<u32>) -> Result<World, SimulationError> {
+        let max = gpu.device.limits().max_texture_dimension_2d;
+        let (width, height) = options.size;
+        let chunk_size = chunk_size.unwrap_or(max).min(max);
+        if width == 0 || height == 0 || chunk_size <= 2 * HALO {
+            return Err(SimulationError::InvalidSize { size: options.size, max });
+        }
+
+        // A world that fits in one chunk is a plain simulation
+        if width.max(height) <= chunk_size {
+            return Ok(World {
+                size: options.size,
+                columns: vec![width],
+                rows: vec![height],
+                chunks: vec![Simulation::on_gpu(gpu, options)?],
+                halo: 0,
+                options: options.clone(),
+            });
+        }
+        if options.kernel != Kernel::Texture
+            || options.skip_stable_tiles
+            || options.block_generations > 1
+            || options.boundary != Boundary::Torus
+        {
+            return Err(SimulationError::ChunkedWorld);
+        }
+
+        // Split each side into nearly equal chunks whose textures fit the chunk size. The
+        // chunks only differ in size and share their pipelines.
+        let interior = chunk_size - 2 * HALO;
+        let columns = split(width, interior);
+        let rows = split(height, interior);
+        let pipelines = Arc::new(Pipelines::new(&gpu.device, options));
+        let mut chunks = Vec::with_capacity(columns.len() * rows.len());
+        for &chunk_height in &rows {
+            for &chunk_width in &columns {
+                let chunk_options = SimulationOptions {
+                    size: (chunk_width + 2 * HALO, chunk_height + 2 * HALO),
+                    ..options.clone()
+                };
+                chunks.push(Simulation::with_pipelines(gpu.clone(), Arc::clone(&pipelines), &chunk_options)?);
+            }
+        }
+
+        Ok(World {
+            size: options.size,
+            columns,
+            rows,
+            chunks,
+            halo: HALO,
+            options: options.clone(),
+        })
+    }
+
+    /// The adapter the shared device was created on
+    pub fn adapter(&self) -> &wgpu::Adapter {
+        self.chunks[0].adapter()
+    }
+
+    /// The device shared by the chunks
+    pub fn device(&self) -> &wgpu::Device {
+        self.chunks[0].device()
+    }
+
+    /// The queue of the shared device
+    pub fn queue(&self) -> &wgpu::Queue {
+        self.chunks[0].queue()
+    }
+
+    /// Width and height of the world in cells
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    /// How the cells are stored in the chunk textures
+    pub fn format(&self) -> CellFormat {
+        self.options.format
+    }
+
+    /// Generations advanced since the world was created or reset
+    pub fn generation(&self) -> u64 {
+        self.chunks[0].generation()
+    }
+
+    /// The rule used by the next generation
+    pub fn rule(&self) -> Rule {
+        self.chunks[0].rule()
+    }
+
+    /// Cells around the interior of each chunk texture, zero for a single chunk
+    pub fn halo(&self) -> u32 {
+        self.halo
+    }
+
+    /// The chunks with the position and size of their interiors in the world. The
+    /// interior of a chunk starts `halo` cells into its textures.
+    pub fn chunks(&self) -> impl Iterator<Item = ((u32, u32), (u32, u32), &Simulation)> + '_ {
+        self.chunks.iter().enumerate().map(|(index, chunk)| {
+            let (column, row) = (index % self.columns.len(), index / self.columns.len());
+            let origin = (self.columns[..column].iter().sum(), self.rows[..row].iter().sum());
+            (origin, (self.columns[column], self.rows[row]), chunk)
+        })
+    }
+
+    /// Switches the rule used by the following generations
+    pub fn set_rule(&mut self, rule: Rule) {
+        for chunk in &mut self.chunks {
+            chunk.set_rule(rule);
+        }
+    }
+
+    /// Switches how the edges are joined. A world of several chunks is always a torus.
+    pub fn set_boundary(&mut self, boundary: Boundary) -> Result<(), SimulationError> {
+        match self.chunks.as_mut_slice() {
+            [chunk] => chunk.set_boundary(boundary),
+            _ if boundary == Boundary::Torus => {},
+            _ => return Err(SimulationError::ChunkedWorld),
+        }
+        Ok(())
+    }
+
+    /// Changes the size of a world of a single chunk, see `Simulation::resize`
+    pub fn resize(&mut self, size: (u32, u32), anchor: Anchor) -> Result<(), SimulationError> {
+        let [chunk] = self.chunks.as_mut_slice() else {
+            return Err(SimulationError::ChunkedWorld);
+        };
+        chunk.resize(size, anchor)?;
+        self.size = size;
+        self.columns = vec![size.0];
+        self.rows = vec![size.1];
+        self.options.size = size;
+        Ok(())
+    }
+
+    /// Whether the device was lost and `recover` must be called before stepping
+    pub fn is_device_lost(&self) -> bool {
+        self.chunks.iter().any(Simulation::is_device_lost)
+    }
+
+    /// Takes the first wgpu error raised since the last call, see `Simulation::take_error`
+    pub fn take_error(&self) -> Option<wgpu::Error> {
+        self.chunks.iter().find_map(Simulation::take_error)
+    }
+
+    /// Starts reading back every chunk for `recover`, see `Simulation::take_snapshot`
+    pub fn take_snapshot(&mut self) {
+        for chunk in &mut self.chunks {
+            chunk.take_snapshot();
+        }
+    }
+
+    /// Keeps the snapshots whose copies are done, see `Simulation::poll_snapshot`
+    pub fn poll_snapshot(&mut self) -> bool {
+        self.chunks.iter_mut().fold(false, |pending, chunk| chunk.poll_snapshot() | pending)
+    }
+
+    /// Recreates the device and the chunks after the device was lost, see
+    /// `Simulation::recover`
+    pub async fn recover(
+        &mut self,
+        instance: &wgpu::Instance,
+        compatible_surface: Option<&wgpu::Surface<'_>>,
+    ) -> Result<(), SimulationError> {
+        let gpu = Gpu::request(instance, compatible_surface, &self.options).await?;
+        let pipelines = Arc::new(Pipelines::new(&gpu.device, &self.options));
+        for chunk in &mut self.chunks {
+            chunk.recover_on(gpu.clone(), Arc::clone(&pipelines))?;
+        }
+        Ok(())
+    }
+
+    /// Records the given number of generations into the encoder, exchanging the halos
+    /// before each one. See `Simulation::transition`.
+    pub fn transition(&mut self, encoder: &mut wgpu::CommandEncoder, generations: u32) {
+        if let [chunk] = self.chunks.as_mut_slice() {
+            chunk.transition(encoder, generations);
+            return;
+        }
+        for _ in 0..generations {
+            self.encode_halo_exchange(encoder);
+            for chunk in &mut self.chunks {
+                chunk.transition(encoder, 1);
+            }
+        }
+    }
+
+    /// Advances the world by the given number of generations in a single submission
+    pub fn step(&mut self, generations: u32) {
+        let mut encoder = self.device().create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("world_transition_encoder"),
+        });
+        self.transition(&mut encoder, generations);
+        self.queue().submit([encoder.finish()]);
+    }
+
+    // Copies the cells along the edges of every chunk into the halos of its neighbors,
+    // see `stage_edges` and `copy_halo`
+    fn encode_halo_exchange(&self, encoder: &mut wgpu::CommandEncoder) {
+        let chunk_size = |index: usize| (self.columns[index % self.columns.len()], self.rows[index / self.columns.len()]);
+
+        for (index, chunk) in self.chunks.iter().enumerate() {
+            stage_edges(encoder, chunk, chunk_size(index));
+        }
+
+        let (columns, rows) = (self.columns.len() as i64, self.rows.len() as i64);
+        for (index, chunk) in self.chunks.iter().enumerate() {
+            let (column, row) = (index as i64 % columns, index as i64 / columns);
+            for offset @ (dx, dy) in NEIGHBORS {
+                let neighbor_index = ((row + dy).rem_euclid(rows) * columns + (column + dx).rem_euclid(columns)) as usize;
+                let neighbor = self.chunks[neighbor_index].write_texture();
+                copy_halo(encoder, (chunk, chunk_size(index)), (neighbor, chunk_size(neighbor_index)), offset);
+            }
+        }
+    }
+
+    /// Overwrites a region of the current generation, wrapping around the torus
+    pub fn set_region(&mut self, (x, y): (u32, u32), grid: &Grid) {
+        let columns = self.columns.len();
+        for (source_y, row, local_y, rows) in chunk_spans(y, grid.height(), &self.rows) {
+            for (source_x, column, local_x, width) in chunk_spans(x, grid.width(), &self.columns) {
+                let part = grid.crop((source_x, source_y), (width, rows));
+                self.chunks[row * columns + column].set_region((local_x + self.halo, local_y + self.halo), &part);
+            }
+        }
+    }
+
+    /// Overwrites a single cell of the current generation
+    pub fn set_cell(&mut self, position: (u32, u32), cell: Cell) {
+        self.set_region(position, &Grid::from_cells(1, 1, vec![cell]));
+    }
+
+    /// Overwrites the region of the pattern with its top left corner at `origin`
+    pub fn place_pattern(&mut self, origin: (u32, u32), pattern: &Pattern) {
+        self.set_region(origin, &pattern.to_grid());
+    }
+
+    /// Places the pattern in the middle of the world
+    pub fn center_pattern(&mut self, pattern: &Pattern) {
+        let (width, height) = self.size;
+        let origin = (
+            width.saturating_sub(pattern.width()) / 2,
+            height.saturating_sub(pattern.height()) / 2,
+        );
+        self.place_pattern(origin, pattern);
+    }
+
+    /// Replaces the whole world, which must match its size
+    pub fn set_grid(&mut self, grid: &Grid) {
+        assert_eq!((grid.width(), grid.height()), self.size, "grid size does not match world size");
+        match self.chunks.as_mut_slice() {
+            [chunk] => chunk.set_grid(grid),
+            _ => self.set_region((0, 0), grid),
+        }
+    }
+
+    /// Kills every cell and starts over at generation zero
+    pub fn reset(&mut self) {
+        for chunk in &mut self.chunks {
+            chunk.reset();
+        }
+    }
+
+    /// Fills the world using the generator, see `Simulation::randomize`. Soups are
+    /// generated on the GPU chunk by chunk, the other generators build the whole world
+    /// on the CPU.
+    pub fn randomize(&mut self, generator: Generator, seed: u64, density: f64) {
+        match generator {
+            Generator::Soup => {
+                let (width, height) = self.size;
+                let origins: Vec<_> = self.chunks().map(|(origin, _, _)| origin).collect();
+                for (chunk, (x, y)) in self.chunks.iter_mut().zip(origins) {
+                    // Texel (0, 0) is in the halo before the interior
+                    let origin = ((x + width - self.halo) % width, (y + height - self.halo) % height);
+                    chunk.randomize_soup(seed, density, origin, self.size);
+                }
+            },
+            _ => self.set_grid(&generator.generate(self.size, seed, density)),
+        }
+    }
+
+    /// Copies the current generation of every chunk back to the CPU, see
+    /// `Simulation::read_cells`
+    pub fn read_cells(&self) -> Result<Grid, wgpu::BufferAsyncError> {
+        let (width, height) = self.size;
+        let mut grid = Grid::new(width, height);
+        for ((x, y), (chunk_width, chunk_height), chunk) in self.chunks() {
+            let cells = chunk.read_cells_blocking()?;
+            for row in 0..chunk_height {
+                for column in 0..chunk_width {
+                    grid.set(x + column, y + row, cells.get(column + self.halo, row + self.halo));
+                }
+            }
+        }
+        Ok(grid)
+    }
+
+    /// Reads back the whole world, or the (origin, size) selection, as a pattern tagged
+    /// with the rule being simulated
+    pub fn export_pattern(&self, selection: Option<((u32, u32), (u32, u32))>) -> Result<Pattern, wgpu::BufferAsyncError> {
+        let grid = self.read_cells()?;
+        let (origin, size) = selection.unwrap_or(((0, 0), self.size));
+        let mut pattern = Pattern::from_grid(&grid, origin, size);
+        pattern.set_rule(Some(self.rule()));
+        Ok(pattern)
+    }
+}
+
+// Splits `length` cells into the fewest nearly equal parts of at most `max` cells
+fn split(length: u32, max: u32) -> Vec<u32> {
+    let parts = length.div_ceil(max);
+    (0..parts).map(|part| length / parts + u32::from(part < length % parts)).collect()
+}
+
+// Splits a span of `length` cells starting at `start` on a torus divided into chunks of
+// the given lengths into (offset in span, chunk, position in chunk, length) pieces
+fn chunk_spans(start: u32, length: u32, chunks: &[u32]) -> Vec<(u32, usize, u32, u32)> {
+    let size = chunks.iter().sum();
+    let mut spans = Vec::new();
+    for (offset, position, length) in wrapped_spans(start, length.min(size), size) {
+        let mut chunk_start = 0;
+        for (chunk, &chunk_length) in chunks.iter().enumerate() {
+            let begin = position.max(chunk_start);
+            let end = (position + length).min(chunk_start + chunk_length);
+            if begin < end {
+                spans.push((offset + begin - position, chunk, begin - chunk_start, end - begin));
+            }
+            chunk_start += chunk_length;
+        }
+    }
+    spans
+}
+
+// The offsets of the chunks around a chunk
+pub(crate) const NEIGHBORS: [(i64, i64); 8] = [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)];
+
+// Copies the cells along the edges of the interior of a chunk of the given interior size
+// from its read texture into its write texture, before `copy_halo`. A chunk can be its
+// own neighbor and a texture cannot be copied onto itself, so the edges are staged in
+// the write texture, which the next generation overwrites anyway.
+pub(crate) fn stage_edges(encoder: &mut wgpu::CommandEncoder, chunk: &Simulation, (width, height): (u32, u32)) {
+    let edges = [((1, 1), (width, 1)), ((1, height), (width, 1)), ((1, 1), (1, height)), ((width, 1), (1, height))];
+    for (origin, size) in edges {
+        copy_region(encoder, chunk.read_texture(), origin, chunk.write_texture(), origin, size);
+    }
+}
+
+// Copies the staged edge of the neighbor at `offset` in chunks into the halo of a chunk,
+// given with the interior sizes of both
+pub(crate) fn copy_halo(
+    encoder: &mut wgpu::CommandEncoder,
+    (chunk, (width, height)): (&Simulation, (u32, u32)),
+    (neighbor, (neighbor_width, neighbor_height)): (&wgpu::Texture, (u32, u32)),
+    (dx, dy): (i64, i64),
+) {
+    // Along each axis the halo before the interior takes the last interior cells of the
+    // previous chunk and the halo after it the first ones of the next chunk, as
+    // (position in the neighbor, position in the halo, length)
+    let axis = |d, length, neighbor_length| match d {
+        -1 => (neighbor_length, 0, 1),
+        0 => (1, 1, length),
+        _ => (1, length + 1, 1),
+    };
+    let (source_x, target_x, copy_width) = axis(dx, width, neighbor_width);
+    let (source_y, target_y, copy_height) = axis(dy, height, neighbor_height);
+    copy_region(
+        encoder,
+        neighbor,
+        (source_x, source_y),
+        chunk.read_texture(),
+        (target_x, target_y),
+        (copy_width, copy_height),
+    );
+}
+
+fn copy_region(
+    encoder: &mut wgpu::CommandEncoder,
+    source: &wgpu::Texture,
+    (source_x, source_y): (u32, u32),
+    target: &wgpu::Texture,
+    (target_x, target_y): (u32, u32),
+    (width, height): (u32, u32),
+) {
+    encoder.copy_texture_to_texture(
+        wgpu::ImageCopyTexture {
+            texture: source,
+            mip_level: 0,
+            origin: wgpu::Origin3d { x: source_x, y: source_y, z: 0 },
+            aspect: wgpu::TextureAspect::All,
+        },
+        wgpu::ImageCopyTexture {
+            texture: target,
+            mip_level: 0,
+            origin: wgpu::Origin3d { x: target_x, y: target_y, z: 0 },
+            aspect: wgpu::TextureAspect::All,
+        },
+        wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reference::{first_difference, step_n};
+    use crate::testing::{gpu_world, grid_with, GLIDER};
+
+    #[test]
+    fn chunks_cover_the_torus() {
+        assert_eq!(split(100, 32), vec![25, 25, 25, 25]);
+        assert_eq!(split(70, 32), vec![24, 23, 23]);
+        assert_eq!(split(30, 32), vec![30]);
+
+        let chunks = [25, 25, 25, 25];
+        assert_eq!(chunk_spans(20, 10, &chunks), vec![(0, 0, 20, 5), (5, 1, 0, 5)]);
+        assert_eq!(chunk_spans(95, 10, &chunks), vec![(0, 3, 20, 5), (5, 0, 0, 5)]);
+        assert_eq!(chunk_spans(0, 120, &chunks).iter().map(|span| span.3).sum::<u32>(), 100);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter, run with --ignored"]
+    fn chunked_worlds_match() {
+        // A single column or row of chunks is its own neighbor
+        for (size, chunks) in [((100, 70), 12), ((20, 70), 3), ((70, 20), 3), ((30, 30), 1)] {
+            let mut world = gpu_world(size, 32);
+            assert_eq!(world.chunks().count(), chunks);
+            let grid = Generator::Soup.generate(size, 5, 0.35);
+            world.set_grid(&grid);
+            assert_eq!(world.read_cells().unwrap(), grid);
+
+            world.step(25);
+            let expected = step_n(&grid, Rule::CONWAY, Boundary::Torus, 25);
+            let actual = world.read_cells().unwrap();
+            assert_eq!(first_difference(&expected, &actual), None, "{:?}", size);
+            assert_eq!(world.generation(), 25);
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter, run with --ignored"]
+    fn chunked_world_wraps_regions_and_soups() {
+        let mut world = gpu_world((100, 70), 32);
+        world.randomize(Generator::Soup, 1234, 0.4);
+        assert_eq!(world.read_cells().unwrap(), Generator::Soup.generate((100, 70), 1234, 0.4));
+
+        // A glider placed across the corner of the torus crosses every chunk seam
+        world.reset();
+        world.set_region((98, 68), &grid_with(3, 3, GLIDER, (0, 0)));
+        world.step(40);
+        let expected = step_n(&grid_with(100, 70, GLIDER, (98, 68)), Rule::CONWAY, Boundary::Torus, 40);
+        assert_eq!(first_difference(&expected, &world.read_cells().unwrap()), None);
+
+        assert!(matches!(world.set_boundary(Boundary::Mirror), Err(SimulationError::ChunkedWorld)));
+        assert!(matches!(world.resize((50, 50), Anchor::Center), Err(SimulationError::ChunkedWorld)));
+    }
+}